@@ -0,0 +1,126 @@
+//! Headless simulator: plays games without a terminal and reports timing,
+//! for profiling `Game::step` and `Game::place_apple`.
+//!
+//! Usage: `cargo run --release --bin sim -- --ticks 100000 --seed 7 --width 40 --height 20 [--autopilot]`
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use snake_game::{DirectionEnum, Game, GameEvent, Settings};
+use std::time::{Duration, Instant};
+
+/// Calls to `place_apple` timed after the run
+const PLACE_APPLE_CALLS: u32 = 10_000;
+/// Chance per tick that the random driver picks a new direction
+const TURN_CHANCE: f64 = 0.2;
+
+/// Command-line options
+struct SimArgs {
+    ticks: u64,
+    seed: u64,
+    width: u16,
+    height: u16,
+    autopilot: bool,
+}
+
+impl SimArgs {
+    /// Parses `--ticks`, `--seed`, `--width`, `--height` and `--autopilot`
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = SimArgs {
+            ticks: 10_000,
+            seed: 0,
+            width: 40,
+            height: 20,
+            autopilot: false,
+        };
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--ticks" => parsed.ticks = parse_value(&flag, args.next())?,
+                "--seed" => parsed.seed = parse_value(&flag, args.next())?,
+                "--width" => parsed.width = parse_value(&flag, args.next())?,
+                "--height" => parsed.height = parse_value(&flag, args.next())?,
+                "--autopilot" => parsed.autopilot = true,
+                _ => return Err(format!("unknown argument: {}", flag)),
+            }
+        }
+        if parsed.width < 10 || parsed.height < 5 {
+            return Err("the board must be at least 10x5".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+/// Parses the value that follows a flag
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} expects a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+/// Starts a game that moves from the first tick
+fn new_game(args: &SimArgs, settings: &Settings, seed: u64) -> Game {
+    let mut game = Game::from_board(args.width, args.height, settings, seed);
+    game.countdown = None;
+    game.autopilot = args.autopilot;
+    game
+}
+
+fn main() {
+    let args = match SimArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let settings = Settings::default();
+    // Random steering uses its own stream so the game's apples stay seeded
+    let mut driver = StdRng::seed_from_u64(args.seed);
+    let mut game = new_game(&args, &settings, args.seed);
+    let mut games = 1;
+    let mut best = 0;
+    let mut apples = 0;
+    let mut stepping = Duration::ZERO;
+
+    for _ in 0..args.ticks {
+        if game.game_over {
+            best = best.max(game.score);
+            game = new_game(&args, &settings, args.seed + games);
+            games += 1;
+        }
+        if !args.autopilot && driver.gen_bool(TURN_CHANCE) {
+            let d = DirectionEnum::ALL[driver.gen_range(0..DirectionEnum::ALL.len())];
+            game.set_direction(0, d);
+        }
+        let start = Instant::now();
+        game.step();
+        stepping += start.elapsed();
+        apples += game
+            .drain_events()
+            .iter()
+            .filter(|e| matches!(e, GameEvent::AppleEaten { .. }))
+            .count();
+    }
+    best = best.max(game.score);
+
+    let start = Instant::now();
+    for _ in 0..PLACE_APPLE_CALLS {
+        game.place_apple();
+    }
+    let placing = start.elapsed();
+
+    println!(
+        "{} ticks, {} games, {} apples, best score {}",
+        args.ticks, games, apples, best
+    );
+    println!(
+        "step:        {:?} total, {:?} per tick",
+        stepping,
+        stepping / args.ticks.max(1) as u32
+    );
+    println!(
+        "place_apple: {:?} total, {:?} per call",
+        placing,
+        placing / PLACE_APPLE_CALLS
+    );
+}
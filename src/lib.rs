@@ -0,0 +1,5882 @@
+//! Game rules for the terminal snake game: board state, settings, replays
+//! and saved data. Shared by the TUI (`main.rs`) and the headless `sim` binary.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Represents a position (x, y) on the board
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Seconds counted down before the snake starts moving
+pub const COUNTDOWN_FROM: u8 = 3;
+/// Maximum number of turns buffered between ticks
+pub const INPUT_BUFFER_SIZE: usize = 3;
+/// Chance that eating an apple also spawns a golden apple
+pub const SPECIAL_APPLE_CHANCE: f64 = 0.15;
+/// Ticks a golden apple stays on the board before vanishing
+///
+/// Counted in ticks rather than wall-clock time so replays stay deterministic.
+pub const SPECIAL_APPLE_TTL: u64 = 40;
+/// Points awarded for eating a golden apple
+pub const SPECIAL_APPLE_POINTS: u32 = 5;
+/// Points awarded for eating a 2x2 big apple
+pub const BIG_APPLE_POINTS: u32 = 3;
+/// Apples eaten within this long of the previous one extend the combo.
+/// Wall-clock rather than ticks so the window doesn't shrink as the game
+/// speeds up; replays keep the combos they were played with instead of
+/// timing them again.
+pub const COMBO_WINDOW: Duration = Duration::from_millis(2500);
+/// Steps `--debug` can undo
+pub const HISTORY_DEPTH: usize = 100;
+/// Speedrun splits are taken each time the score passes a multiple of this
+pub const SPLIT_EVERY: u32 = 10;
+/// Free cells a respawned snake needs ahead of its head
+pub const RESPAWN_CLEARANCE: u16 = 5;
+/// Largest combo multiplier
+pub const MAX_COMBO: u32 = 5;
+/// How long the combo counter flashes after it goes up
+pub const COMBO_FLASH: Duration = Duration::from_millis(600);
+/// Steps the score carry-over setting moves in, as a percentage
+pub const CARRY_OVER_STEP: u32 = 25;
+/// Largest share of a lost game's score that carries into the next
+pub const MAX_CARRY_OVER: u32 = 75;
+/// Steps the bullet time slowdown moves in, as a percentage of a tick
+pub const BULLET_TIME_STEP: u32 = 50;
+/// Strongest bullet time slowdown, as a percentage of a tick
+pub const MAX_BULLET_TIME: u32 = 300;
+/// Farthest ahead bullet time looks for a crash
+pub const MAX_BULLET_DISTANCE: u16 = 3;
+/// Ticks in a row bullet time may slow; it then waits for a clear path so
+/// hugging walls can't stall the game
+pub const BULLET_TIME_TICKS: u16 = 4;
+/// Highest points-per-apple the settings screen offers
+pub const MAX_APPLE_VALUE: u32 = 10;
+/// Chance that eating an apple also spawns a poison item
+pub const POISON_CHANCE: f64 = 0.2;
+/// Chance that eating an apple also drops an apple magnet, when enabled
+pub const MAGNET_CHANCE: f64 = 0.1;
+/// Ticks an eaten magnet keeps pulling the apple, counted in ticks so
+/// replays stay deterministic
+pub const MAGNET_TICKS: u64 = 40;
+/// Tail segments removed when poison is eaten
+pub const POISON_SHRINK: usize = 2;
+/// Default snake length at the start of a game
+pub const INITIAL_LENGTH: u16 = 3;
+/// Longest starting length offered in settings
+pub const MAX_START_LENGTH: u16 = 20;
+/// Points needed to gain a level
+pub const POINTS_PER_LEVEL: u32 = 5;
+/// Fastest the game ever ticks, in milliseconds
+pub const MIN_TICK_MS: u64 = 40;
+/// Slowest base speed reachable with the live speed keys, in milliseconds
+pub const MAX_TICK_MS: u64 = 400;
+/// Base speed change per press of a live speed key, in milliseconds
+pub const TICK_ADJUST_MS: u64 = 10;
+/// Points scored between new wall tiles when closing walls are on
+pub const WALL_EVERY: u32 = 3;
+/// Most wall tiles a board ever gets (also capped at a tenth of the board)
+pub const MAX_WALLS: usize = 30;
+/// Ticks a ghost trail cell stays visible after the tail leaves it
+pub const TRAIL_LIFE: u8 = 5;
+/// Entries kept on the leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+/// Characters in a leaderboard name
+pub const INITIALS_LEN: usize = 3;
+/// Format version written in the leaderboard file's header
+pub const LEADERBOARD_VERSION: u32 = 1;
+/// Format version written in the high score file's header
+pub const HIGH_SCORE_VERSION: u32 = 1;
+/// Largest fog radius offered in settings
+pub const MAX_FOG_RADIUS: u16 = 15;
+/// Ticks between each ring of the shrinking border closing in
+///
+/// Counted in ticks rather than wall-clock time so replays stay deterministic.
+pub const SHRINK_EVERY: u64 = 100;
+/// Narrowest board a game is played on
+pub const MIN_BOARD_WIDTH: u16 = 10;
+/// Shortest board a game is played on
+pub const MIN_BOARD_HEIGHT: u16 = 5;
+/// Smallest open arena side the shrinking border leaves
+pub const MIN_ARENA: u16 = 4;
+
+/// Golden bonus apple that despawns if not eaten in time
+#[derive(Clone)]
+pub struct SpecialApple {
+    pub pos: Point,
+    pub expires_at: u64,
+}
+
+/// Snake movement directions
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DirectionEnum {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl DirectionEnum {
+    /// Lowercase name used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            DirectionEnum::Up => "up",
+            DirectionEnum::Down => "down",
+            DirectionEnum::Left => "left",
+            DirectionEnum::Right => "right",
+        }
+    }
+
+    /// Parses a name written by [`DirectionEnum::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    pub const ALL: [DirectionEnum; 4] = [
+        DirectionEnum::Up,
+        DirectionEnum::Down,
+        DirectionEnum::Left,
+        DirectionEnum::Right,
+    ];
+
+    /// Head glyph pointing this way, in Unicode or plain ASCII
+    pub fn arrow(self, ascii: bool) -> &'static str {
+        match (self, ascii) {
+            (DirectionEnum::Up, false) => "▲",
+            (DirectionEnum::Down, false) => "▼",
+            (DirectionEnum::Left, false) => "◀",
+            (DirectionEnum::Right, false) => "▶",
+            (DirectionEnum::Up, true) => "^",
+            (DirectionEnum::Down, true) => "v",
+            (DirectionEnum::Left, true) => "<",
+            (DirectionEnum::Right, true) => ">",
+        }
+    }
+
+    /// The heading a quarter turn away, clockwise or counter-clockwise
+    pub fn rotated(self, clockwise: bool) -> DirectionEnum {
+        match (self, clockwise) {
+            (DirectionEnum::Up, true) | (DirectionEnum::Down, false) => DirectionEnum::Right,
+            (DirectionEnum::Right, true) | (DirectionEnum::Left, false) => DirectionEnum::Down,
+            (DirectionEnum::Down, true) | (DirectionEnum::Up, false) => DirectionEnum::Left,
+            (DirectionEnum::Left, true) | (DirectionEnum::Right, false) => DirectionEnum::Up,
+        }
+    }
+
+    /// True when `other` points the opposite way
+    pub fn is_reverse_of(self, other: DirectionEnum) -> bool {
+        matches!(
+            (self, other),
+            (DirectionEnum::Up, DirectionEnum::Down)
+                | (DirectionEnum::Down, DirectionEnum::Up)
+                | (DirectionEnum::Left, DirectionEnum::Right)
+                | (DirectionEnum::Right, DirectionEnum::Left)
+        )
+    }
+}
+
+/// Colors a theme can use; the terminal UI maps them onto its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    Rgb(u8, u8, u8),
+    /// An entry of the 256-color palette
+    Indexed(u8),
+}
+
+/// Color palette used when drawing the game screen
+pub struct Theme {
+    pub name: &'static str,
+    pub title: Color,
+    pub score: Color,
+    pub level: Color,
+    pub border: Color,
+    pub snake_head: Color,
+    pub snake_body: Color,
+    pub apple: Color,
+    pub special_apple: Color,
+    pub poison: Color,
+    pub portal: Color,
+    pub background: Color,
+    pub grid: Color,
+    pub alert: Color,
+    pub rival_head: Color,
+    pub rival_body: Color,
+    pub head_glyph: &'static str,
+    pub body_glyph: &'static str,
+    pub apple_glyph: &'static str,
+    /// Always shade the board as a grid, whatever the checkerboard setting
+    pub pixel_grid: bool,
+    /// Colors the border shifts through as the score rises; empty keeps it
+    /// at `border`
+    pub border_gradient: &'static [(u8, u8, u8)],
+}
+
+/// Built-in palettes, selectable from settings or `--theme`
+pub const THEMES: [Theme; 6] = [
+    Theme {
+        name: "Classic",
+        title: Color::Yellow,
+        score: Color::LightGreen,
+        level: Color::Cyan,
+        border: Color::Magenta,
+        snake_head: Color::LightGreen,
+        snake_body: Color::Green,
+        apple: Color::Red,
+        special_apple: Color::LightYellow,
+        poison: Color::Magenta,
+        portal: Color::Cyan,
+        background: Color::Black,
+        grid: Color::Indexed(235),
+        alert: Color::Red,
+        rival_head: Color::LightBlue,
+        rival_body: Color::Blue,
+        head_glyph: "■",
+        body_glyph: "■",
+        apple_glyph: "@",
+        pixel_grid: false,
+        border_gradient: &[(0xc0, 0x30, 0xc0), (0xe0, 0x30, 0x30), (0xf0, 0xd0, 0x30)],
+    },
+    Theme {
+        name: "Monochrome",
+        title: Color::White,
+        score: Color::White,
+        level: Color::Gray,
+        border: Color::Gray,
+        snake_head: Color::White,
+        snake_body: Color::Gray,
+        apple: Color::White,
+        special_apple: Color::White,
+        poison: Color::DarkGray,
+        portal: Color::White,
+        background: Color::Black,
+        grid: Color::Indexed(235),
+        alert: Color::White,
+        rival_head: Color::DarkGray,
+        rival_body: Color::DarkGray,
+        head_glyph: "■",
+        body_glyph: "■",
+        apple_glyph: "@",
+        pixel_grid: false,
+        border_gradient: &[],
+    },
+    Theme {
+        name: "Neon",
+        title: Color::LightMagenta,
+        score: Color::LightCyan,
+        level: Color::LightYellow,
+        border: Color::LightBlue,
+        snake_head: Color::LightCyan,
+        snake_body: Color::Cyan,
+        apple: Color::LightMagenta,
+        special_apple: Color::LightYellow,
+        poison: Color::LightBlue,
+        portal: Color::LightGreen,
+        background: Color::Black,
+        grid: Color::Indexed(235),
+        alert: Color::LightRed,
+        rival_head: Color::LightYellow,
+        rival_body: Color::Yellow,
+        head_glyph: "■",
+        body_glyph: "■",
+        apple_glyph: "@",
+        pixel_grid: false,
+        border_gradient: &[(0x50, 0x70, 0xff), (0xff, 0x40, 0xff), (0x40, 0xff, 0xff)],
+    },
+    // Colorblind-friendly: blue/yellow contrast plus distinct shapes, so
+    // the apple and snake stay apart even in grayscale
+    Theme {
+        name: "High Contrast",
+        title: Color::White,
+        score: Color::LightYellow,
+        level: Color::LightBlue,
+        border: Color::White,
+        snake_head: Color::White,
+        snake_body: Color::LightBlue,
+        apple: Color::LightYellow,
+        special_apple: Color::White,
+        poison: Color::Blue,
+        portal: Color::LightCyan,
+        background: Color::Black,
+        grid: Color::Indexed(235),
+        alert: Color::LightYellow,
+        rival_head: Color::LightRed,
+        rival_body: Color::Red,
+        head_glyph: "█",
+        body_glyph: "▒",
+        apple_glyph: "◆",
+        pixel_grid: false,
+        border_gradient: &[],
+    },
+    // Soft greens and blues for zen mode
+    Theme {
+        name: "Zen",
+        title: Color::LightCyan,
+        score: Color::LightGreen,
+        level: Color::Gray,
+        border: Color::DarkGray,
+        snake_head: Color::LightGreen,
+        snake_body: Color::Green,
+        apple: Color::LightBlue,
+        special_apple: Color::LightCyan,
+        poison: Color::Blue,
+        portal: Color::Cyan,
+        background: Color::Black,
+        grid: Color::Indexed(235),
+        alert: Color::LightCyan,
+        rival_head: Color::LightBlue,
+        rival_body: Color::Blue,
+        head_glyph: "●",
+        body_glyph: "•",
+        apple_glyph: "o",
+        pixel_grid: false,
+        border_gradient: &[(0x58, 0x58, 0x58), (0x50, 0x90, 0x70), (0x70, 0xc0, 0xe0)],
+    },
+    // Old phone LCD: one dark ink on pale green, blocky glyphs and a faint
+    // pixel grid; head and body share a color like the real thing
+    Theme {
+        name: "Nokia",
+        title: Color::Rgb(0x43, 0x52, 0x3d),
+        score: Color::Rgb(0x43, 0x52, 0x3d),
+        level: Color::Rgb(0x43, 0x52, 0x3d),
+        border: Color::Rgb(0x43, 0x52, 0x3d),
+        snake_head: Color::Rgb(0x43, 0x52, 0x3d),
+        snake_body: Color::Rgb(0x43, 0x52, 0x3d),
+        apple: Color::Rgb(0x43, 0x52, 0x3d),
+        special_apple: Color::Rgb(0x43, 0x52, 0x3d),
+        poison: Color::Rgb(0x43, 0x52, 0x3d),
+        portal: Color::Rgb(0x43, 0x52, 0x3d),
+        background: Color::Rgb(0xc7, 0xf0, 0xd8),
+        grid: Color::Rgb(0xbb, 0xe3, 0xcb),
+        alert: Color::Rgb(0x43, 0x52, 0x3d),
+        rival_head: Color::Rgb(0x43, 0x52, 0x3d),
+        rival_body: Color::Rgb(0x43, 0x52, 0x3d),
+        head_glyph: "█",
+        body_glyph: "█",
+        apple_glyph: "●",
+        pixel_grid: true,
+        border_gradient: &[],
+    },
+];
+
+/// Points at which the border reaches the last color of its gradient
+pub const BORDER_GRADIENT_SCORE: u32 = 50;
+
+impl Theme {
+    /// Border color for a game at `score`, blended along the theme's
+    /// gradient; terminals without 24-bit color keep the fixed `border`
+    pub fn border_color(&self, score: u32, truecolor: bool) -> Color {
+        let stops = self.border_gradient;
+        if !truecolor || stops.is_empty() {
+            return self.border;
+        }
+        // Position along the gradient in 1/BORDER_GRADIENT_SCORE steps
+        let pos = score.min(BORDER_GRADIENT_SCORE) * (stops.len() as u32 - 1);
+        let i = (pos / BORDER_GRADIENT_SCORE) as usize;
+        let Some(&(r2, g2, b2)) = stops.get(i + 1) else {
+            let (r, g, b) = stops[i];
+            return Color::Rgb(r, g, b);
+        };
+        let (r1, g1, b1) = stops[i];
+        let t = pos % BORDER_GRADIENT_SCORE;
+        let mix = |a: u8, b: u8| {
+            (a as u32 * (BORDER_GRADIENT_SCORE - t) + b as u32 * t) as f32
+                / BORDER_GRADIENT_SCORE as f32
+        };
+        Color::Rgb(
+            mix(r1, r2).round() as u8,
+            mix(g1, g2).round() as u8,
+            mix(b1, b2).round() as u8,
+        )
+    }
+}
+
+/// Characters used to draw the board
+pub struct Glyphs {
+    pub head: &'static str,
+    pub body: &'static str,
+    pub apple: &'static str,
+    pub special_apple: &'static str,
+    pub poison: &'static str,
+    pub magnet: &'static str,
+    pub wall: &'static str,
+    pub closed: &'static str,
+    pub portal: &'static str,
+    /// Use `^v<>` rather than Unicode arrows for directional heads
+    pub ascii: bool,
+}
+
+impl Glyphs {
+    /// The theme's glyphs, or single-width ASCII ones for limited terminals
+    pub fn new(theme: &Theme, ascii: bool) -> Self {
+        if ascii {
+            Glyphs {
+                head: "#",
+                body: "o",
+                apple: "*",
+                special_apple: "$",
+                poison: "%",
+                magnet: "U",
+                wall: "+",
+                closed: ":",
+                portal: "O",
+                ascii,
+            }
+        } else {
+            Glyphs {
+                head: theme.head_glyph,
+                body: theme.body_glyph,
+                apple: theme.apple_glyph,
+                special_apple: "$",
+                poison: "%",
+                magnet: "∪",
+                wall: "#",
+                closed: "░",
+                portal: "O",
+                ascii,
+            }
+        }
+    }
+}
+
+/// Index of the colorblind-friendly theme used by `--colorblind`
+pub const HIGH_CONTRAST_THEME: usize = 3;
+/// Index of the calm theme always used in zen mode
+pub const ZEN_THEME: usize = 4;
+/// Theme name stored in replays when each game picks a random theme
+pub const RANDOM_THEME: &str = "Random";
+
+/// Finds a built-in theme index by name (case-insensitive, `-` for spaces)
+pub fn theme_by_name(name: &str) -> Option<usize> {
+    let name = name.replace(['-', '_'], " ");
+    THEMES
+        .iter()
+        .position(|t| t.name.eq_ignore_ascii_case(&name))
+}
+
+/// How `place_apple` picks the next apple's cell
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ApplePlacement {
+    /// Any free cell, uniformly at random
+    Uniform,
+    /// The best of a few random cells, favouring ones far from the last
+    /// apple and the head
+    Spread,
+}
+
+impl ApplePlacement {
+    pub const ALL: [ApplePlacement; 2] = [ApplePlacement::Uniform, ApplePlacement::Spread];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            ApplePlacement::Uniform => "Uniform",
+            ApplePlacement::Spread => "Spread",
+        }
+    }
+
+    /// Parses a name written by [`ApplePlacement::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Next or previous placement, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+}
+
+/// The choice after (or before) `current` in `all`, wrapping around; the
+/// settings enums cycle through their `ALL` tables with it
+pub fn cycle_in<T: Copy + PartialEq>(all: &[T], current: T, forward: bool) -> T {
+    let len = all.len();
+    let i = all.iter().position(|&c| c == current).unwrap_or(0);
+    all[if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }]
+}
+
+/// Which board edges wrap around to the opposite side; the rest are solid
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WrapMode {
+    None,
+    /// Left and right edges wrap
+    Horizontal,
+    /// Top and bottom edges wrap
+    Vertical,
+    Both,
+}
+
+impl WrapMode {
+    pub const ALL: [WrapMode; 4] = [
+        WrapMode::None,
+        WrapMode::Horizontal,
+        WrapMode::Vertical,
+        WrapMode::Both,
+    ];
+
+    /// Name used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::None => "None",
+            WrapMode::Horizontal => "Horizontal",
+            WrapMode::Vertical => "Vertical",
+            WrapMode::Both => "Both",
+        }
+    }
+
+    /// Parses a name written by [`WrapMode::name`] (case-insensitive); the
+    /// `true`/`false` of older replays map to both and none
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "true" => Some(WrapMode::Both),
+            "false" => Some(WrapMode::None),
+            _ => Self::ALL
+                .into_iter()
+                .find(|m| m.name().eq_ignore_ascii_case(name)),
+        }
+    }
+
+    /// Short description for the menu and settings screen
+    pub fn label(self) -> &'static str {
+        match self {
+            WrapMode::None => "solid",
+            WrapMode::Horizontal => "wrap left/right",
+            WrapMode::Vertical => "wrap top/bottom",
+            WrapMode::Both => "wrap",
+        }
+    }
+
+    /// True if the left and right edges wrap
+    pub fn wraps_x(self) -> bool {
+        matches!(self, WrapMode::Horizontal | WrapMode::Both)
+    }
+
+    /// True if the top and bottom edges wrap
+    pub fn wraps_y(self) -> bool {
+        matches!(self, WrapMode::Vertical | WrapMode::Both)
+    }
+
+    /// Next or previous mode, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+}
+
+/// What the header counts: points, snake length, or both
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScoreDisplay {
+    Score,
+    Length,
+    Both,
+}
+
+impl ScoreDisplay {
+    pub const ALL: [ScoreDisplay; 3] = [
+        ScoreDisplay::Score,
+        ScoreDisplay::Length,
+        ScoreDisplay::Both,
+    ];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoreDisplay::Score => "Score",
+            ScoreDisplay::Length => "Length",
+            ScoreDisplay::Both => "Both",
+        }
+    }
+
+    /// Parses a name written by [`ScoreDisplay::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Next or previous choice, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+}
+
+/// Room the game screen leaves around the board: the outer margin and the
+/// heights of the header and status line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Spacing {
+    /// No margin and single-row header and status line, for small terminals
+    Compact,
+    Normal,
+    /// A wider margin for large terminals
+    Roomy,
+}
+
+impl Spacing {
+    pub const ALL: [Spacing; 3] = [Spacing::Compact, Spacing::Normal, Spacing::Roomy];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            Spacing::Compact => "Compact",
+            Spacing::Normal => "Normal",
+            Spacing::Roomy => "Roomy",
+        }
+    }
+
+    /// Parses a name written by [`Spacing::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Next or previous choice, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+
+    /// Blank cells around the whole game screen
+    pub fn margin(self) -> u16 {
+        match self {
+            Spacing::Compact => 0,
+            Spacing::Normal => 1,
+            Spacing::Roomy => 2,
+        }
+    }
+
+    /// Rows above the board: the title line, then the fill bar
+    pub fn header_height(self) -> u16 {
+        match self {
+            Spacing::Compact => 2,
+            Spacing::Normal | Spacing::Roomy => 3,
+        }
+    }
+
+    /// Rows below the board for the controls line
+    pub fn status_height(self) -> u16 {
+        match self {
+            Spacing::Compact => 1,
+            Spacing::Normal | Spacing::Roomy => 2,
+        }
+    }
+
+    /// Columns and rows left inside the board's border on a terminal of
+    /// the given size
+    pub fn board_area(self, width: u16, height: u16) -> (u16, u16) {
+        let margin = 2 * self.margin() + 2;
+        (
+            width.saturating_sub(margin),
+            height.saturating_sub(margin + self.header_height() + self.status_height()),
+        )
+    }
+}
+
+/// Columns the ruler's row numbers take up on a board `height` rows tall,
+/// including a space
+pub fn ruler_width(height: u16) -> u16 {
+    height.saturating_sub(1).max(1).ilog10() as u16 + 2
+}
+
+/// Outline of the play area; cells outside it act as walls
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BoardShape {
+    Rectangle,
+    /// An ellipse touching all four edges
+    Circle,
+    /// A rhombus with its points at the middle of each edge
+    Diamond,
+}
+
+impl BoardShape {
+    pub const ALL: [BoardShape; 3] = [
+        BoardShape::Rectangle,
+        BoardShape::Circle,
+        BoardShape::Diamond,
+    ];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardShape::Rectangle => "Rectangle",
+            BoardShape::Circle => "Circle",
+            BoardShape::Diamond => "Diamond",
+        }
+    }
+
+    /// Parses a name written by [`BoardShape::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+    }
+
+    /// True if `p` lies inside the shape drawn on a `width` x `height` board
+    pub fn contains(self, p: Point, width: u16, height: u16) -> bool {
+        // Distance of the cell's center from the board's center, scaled so
+        // the edges are at 1.0
+        let dx = (2.0 * p.x as f64 + 1.0 - width as f64) / width as f64;
+        let dy = (2.0 * p.y as f64 + 1.0 - height as f64) / height as f64;
+        match self {
+            BoardShape::Rectangle => true,
+            BoardShape::Circle => dx * dx + dy * dy <= 1.0,
+            BoardShape::Diamond => dx.abs() + dy.abs() <= 1.0,
+        }
+    }
+
+    /// Next or previous shape, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+}
+
+/// Fixed wall layouts that can be added to an ordinary board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WallPreset {
+    None,
+    /// Two bars crossing at the center, each half the board long
+    Cross,
+    /// Walls along the edges with a gap in the middle of each side, the
+    /// only way through when the walls wrap
+    BorderGap,
+}
+
+impl WallPreset {
+    pub const ALL: [WallPreset; 3] = [WallPreset::None, WallPreset::Cross, WallPreset::BorderGap];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            WallPreset::None => "None",
+            WallPreset::Cross => "Cross",
+            WallPreset::BorderGap => "Border gap",
+        }
+    }
+
+    /// Parses a name written by [`WallPreset::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Next or previous preset, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+
+    /// The preset's wall cells on a `width` x `height` board
+    pub fn cells(self, width: u16, height: u16) -> Vec<Point> {
+        match self {
+            WallPreset::None => Vec::new(),
+            WallPreset::Cross => {
+                let (cx, cy) = (width / 2, height / 2);
+                let across = (width / 4..=width * 3 / 4).map(|x| Point { x, y: cy });
+                let down = (height / 4..=height * 3 / 4)
+                    .filter(|&y| y != cy)
+                    .map(|y| Point { x: cx, y });
+                across.chain(down).collect()
+            }
+            WallPreset::BorderGap => {
+                let gap_x = width / 3..width - width / 3;
+                let gap_y = height / 3..height - height / 3;
+                (0..height)
+                    .flat_map(|y| (0..width).map(move |x| Point { x, y }))
+                    .filter(|p| {
+                        let top_or_bottom = p.y == 0 || p.y + 1 == height;
+                        let side = p.x == 0 || p.x + 1 == width;
+                        top_or_bottom && !gap_x.contains(&p.x) || side && !gap_y.contains(&p.y)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Random cells compared by the spread apple placement
+pub const SPREAD_SAMPLES: usize = 8;
+
+/// Speed and growth presets
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Display name, also used in replay files
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Parses a name written by [`Difficulty::name`] (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Tick duration at level one
+    pub fn start_ms(self) -> u64 {
+        match self {
+            Difficulty::Easy => 220,
+            Difficulty::Normal => 160,
+            Difficulty::Hard => 100,
+        }
+    }
+
+    /// Milliseconds taken off the tick for each level gained
+    pub fn speed_step(self) -> u64 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 15,
+        }
+    }
+
+    /// Segments gained per apple
+    pub fn growth(self) -> u16 {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        }
+    }
+
+    /// Next or previous preset, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        cycle_in(&Self::ALL, self, forward)
+    }
+}
+
+/// Options chosen on the settings screen and applied when a game starts
+#[derive(Clone)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub wrap: WrapMode,
+    pub base_tick_ms: u64,
+    pub smooth_speed: bool,
+    pub poison: bool,
+    pub growth_per_apple: u16,
+    /// Ordinary apples on the board at once
+    pub apple_count: usize,
+    pub start_length: u16,
+    pub theme: usize,
+    pub versus: bool,
+    pub portals: bool,
+    pub zen: bool,
+    pub closing_walls: bool,
+    pub grid: bool,
+    pub directional_head: bool,
+    pub rainbow: bool,
+    pub ascii: bool,
+    pub square_cells: bool,
+    /// Times each glyph is repeated (1 or 2) for a chunkier look; on top of
+    /// `square_cells`, which already makes cells two columns wide
+    pub cell_width: u16,
+    pub fog_radius: Option<u16>,
+    pub quick_turns: bool,
+    pub shrinking: bool,
+    pub wall_grace: bool,
+    /// A head running into its own body is fatal; off lets it pass through
+    pub self_collision: bool,
+    pub maze: bool,
+    /// Fixed walls added to the board; a maze takes their place
+    pub wall_preset: WallPreset,
+    pub apple_placement: ApplePlacement,
+    pub big_apples: bool,
+    /// Occasionally drop a magnet that drags the apple toward the snake
+    pub magnets: bool,
+    /// The apple runs around the board instead of sitting still
+    pub apple_moves: bool,
+    /// Faintly show where the next apple will appear
+    pub apple_hints: bool,
+    pub apple_value: u32,
+    pub level_multiplier: bool,
+    /// Crashes a solo game survives is one less than this
+    pub lives: u32,
+    /// Percentage of a lost solo game's score the next game starts with
+    pub carry_over: u32,
+    /// Longest a snake may grow; apples past it still score
+    pub max_length: Option<usize>,
+    /// Ticks near a crash last this percentage of a normal tick
+    pub bullet_time: Option<u32>,
+    /// Cells ahead of the head checked for a crash by bullet time
+    pub bullet_distance: u16,
+    /// Solo games start at a random spot and heading instead of the middle
+    /// facing right
+    pub random_start: bool,
+    pub shape: BoardShape,
+    /// Each game picks its own theme at random, ignoring `theme`
+    pub random_theme: bool,
+    /// Number the board's rows and columns along its edges
+    pub ruler: bool,
+    /// Draw snake bodies with connected box-drawing lines instead of blocks
+    pub line_body: bool,
+    /// Crashed snakes are eaten away from the head before the game-over screen
+    pub death_animation: bool,
+    pub score_display: ScoreDisplay,
+    pub spacing: Spacing,
+    /// Left and right keys turn the snake relative to its heading; up and
+    /// down do nothing
+    pub relative_turns: bool,
+    /// Apple alternates between bright and dim to catch the eye
+    pub pulse_apple: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let difficulty = Difficulty::Normal;
+        Self {
+            difficulty,
+            wrap: WrapMode::None,
+            base_tick_ms: difficulty.start_ms(),
+            smooth_speed: false,
+            poison: false,
+            growth_per_apple: difficulty.growth(),
+            apple_count: 1,
+            start_length: INITIAL_LENGTH,
+            theme: 0,
+            versus: false,
+            portals: false,
+            zen: false,
+            closing_walls: false,
+            grid: false,
+            directional_head: false,
+            rainbow: false,
+            ascii: false,
+            square_cells: false,
+            cell_width: 1,
+            fog_radius: None,
+            quick_turns: false,
+            shrinking: false,
+            wall_grace: false,
+            self_collision: true,
+            maze: false,
+            wall_preset: WallPreset::None,
+            apple_placement: ApplePlacement::Uniform,
+            big_apples: false,
+            magnets: false,
+            apple_moves: false,
+            apple_hints: false,
+            apple_value: 1,
+            level_multiplier: false,
+            lives: 1,
+            carry_over: 0,
+            max_length: None,
+            bullet_time: None,
+            bullet_distance: 1,
+            random_start: false,
+            shape: BoardShape::Rectangle,
+            random_theme: false,
+            ruler: false,
+            line_body: false,
+            death_animation: true,
+            relative_turns: false,
+            score_display: ScoreDisplay::Score,
+            spacing: Spacing::Normal,
+            pulse_apple: false,
+        }
+    }
+}
+
+/// Rows listed on the settings screen
+#[derive(Clone, Copy, PartialEq)]
+pub enum SettingsItem {
+    Difficulty,
+    WrapWalls,
+    StartSpeed,
+    Acceleration,
+    Poison,
+    Growth,
+    StartLength,
+    StartPosition,
+    Theme,
+    ScoreDisplay,
+    Spacing,
+    Players,
+    Controls,
+    Portals,
+    ClosingWalls,
+    Grid,
+    DirectionalHead,
+    BodyStyle,
+    DeathAnimation,
+    PulseApple,
+    Rainbow,
+    Fog,
+    BulletTime,
+    BulletDistance,
+    QuickTurns,
+    Shrinking,
+    WallGrace,
+    BodyPassthrough,
+    Maze,
+    Obstacles,
+    ApplePlacement,
+    BigApples,
+    Magnets,
+    RunnerApple,
+    AppleHints,
+    AppleValue,
+    Multiplier,
+    CarryOver,
+    Shape,
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 39] = [
+        SettingsItem::Difficulty,
+        SettingsItem::WrapWalls,
+        SettingsItem::StartSpeed,
+        SettingsItem::Acceleration,
+        SettingsItem::Poison,
+        SettingsItem::Growth,
+        SettingsItem::StartLength,
+        SettingsItem::StartPosition,
+        SettingsItem::Theme,
+        SettingsItem::ScoreDisplay,
+        SettingsItem::Spacing,
+        SettingsItem::Players,
+        SettingsItem::Controls,
+        SettingsItem::Portals,
+        SettingsItem::ClosingWalls,
+        SettingsItem::Grid,
+        SettingsItem::DirectionalHead,
+        SettingsItem::BodyStyle,
+        SettingsItem::DeathAnimation,
+        SettingsItem::PulseApple,
+        SettingsItem::Rainbow,
+        SettingsItem::Fog,
+        SettingsItem::BulletTime,
+        SettingsItem::BulletDistance,
+        SettingsItem::QuickTurns,
+        SettingsItem::Shrinking,
+        SettingsItem::WallGrace,
+        SettingsItem::BodyPassthrough,
+        SettingsItem::Maze,
+        SettingsItem::Obstacles,
+        SettingsItem::ApplePlacement,
+        SettingsItem::BigApples,
+        SettingsItem::Magnets,
+        SettingsItem::RunnerApple,
+        SettingsItem::AppleHints,
+        SettingsItem::AppleValue,
+        SettingsItem::Multiplier,
+        SettingsItem::CarryOver,
+        SettingsItem::Shape,
+    ];
+
+    /// Label shown next to the value
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsItem::Difficulty => "Difficulty",
+            SettingsItem::WrapWalls => "Walls",
+            SettingsItem::StartSpeed => "Starting speed",
+            SettingsItem::Acceleration => "Acceleration",
+            SettingsItem::Poison => "Poison food",
+            SettingsItem::Growth => "Growth per apple",
+            SettingsItem::StartLength => "Starting length",
+            SettingsItem::StartPosition => "Starting position",
+            SettingsItem::Theme => "Theme",
+            SettingsItem::ScoreDisplay => "Header shows",
+            SettingsItem::Spacing => "Spacing",
+            SettingsItem::Players => "Players",
+            SettingsItem::Controls => "Controls",
+            SettingsItem::Portals => "Portals",
+            SettingsItem::ClosingWalls => "Closing walls",
+            SettingsItem::Grid => "Checkerboard",
+            SettingsItem::DirectionalHead => "Head shows direction",
+            SettingsItem::BodyStyle => "Snake body",
+            SettingsItem::DeathAnimation => "Death animation",
+            SettingsItem::PulseApple => "Pulsing apple",
+            SettingsItem::Rainbow => "Rainbow snake",
+            SettingsItem::Fog => "Fog of war",
+            SettingsItem::BulletTime => "Bullet time",
+            SettingsItem::BulletDistance => "Bullet time range",
+            SettingsItem::QuickTurns => "Quick turns",
+            SettingsItem::Shrinking => "Shrinking border",
+            SettingsItem::WallGrace => "Wall grace",
+            SettingsItem::BodyPassthrough => "Body passthrough",
+            SettingsItem::Maze => "Maze",
+            SettingsItem::Obstacles => "Obstacles",
+            SettingsItem::ApplePlacement => "Apple placement",
+            SettingsItem::BigApples => "Big apples",
+            SettingsItem::Magnets => "Apple magnets",
+            SettingsItem::RunnerApple => "Runner apple",
+            SettingsItem::AppleHints => "Easy hints",
+            SettingsItem::AppleValue => "Apple value",
+            SettingsItem::Multiplier => "Level multiplier",
+            SettingsItem::CarryOver => "Score carry-over",
+            SettingsItem::Shape => "Board shape",
+        }
+    }
+}
+
+impl Settings {
+    /// Palette to draw with; zen mode always uses its own calm theme
+    pub fn active_theme(&self) -> &'static Theme {
+        &THEMES[if self.zen { ZEN_THEME } else { self.theme }]
+    }
+
+    /// Board characters for the active theme, honoring `--ascii`
+    pub fn glyphs(&self) -> Glyphs {
+        Glyphs::new(self.active_theme(), self.ascii)
+    }
+
+    /// Switches to a preset, resetting the speed and growth it controls
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        self.base_tick_ms = difficulty.start_ms();
+        self.growth_per_apple = difficulty.growth();
+    }
+
+    /// Current value of a settings row, formatted for display
+    pub fn value(&self, item: SettingsItem) -> String {
+        match item {
+            SettingsItem::Difficulty => self.difficulty.name().to_string(),
+            SettingsItem::WrapWalls => self.wrap.label().to_string(),
+            SettingsItem::StartSpeed => format!("{} ms/tick", self.base_tick_ms),
+            SettingsItem::Acceleration => if self.smooth_speed {
+                "smooth"
+            } else {
+                "per level"
+            }
+            .to_string(),
+            SettingsItem::Poison => if self.poison { "on" } else { "off" }.to_string(),
+            SettingsItem::Growth => self.growth_per_apple.to_string(),
+            SettingsItem::StartLength => self.start_length.to_string(),
+            SettingsItem::StartPosition => if self.random_start {
+                "random"
+            } else {
+                "center"
+            }
+            .to_string(),
+            SettingsItem::Theme if self.random_theme => "Surprise me".to_string(),
+            SettingsItem::Theme => THEMES[self.theme].name.to_string(),
+            SettingsItem::Players => if self.versus { "2 (versus)" } else { "1" }.to_string(),
+            SettingsItem::ScoreDisplay => self.score_display.name().to_string(),
+            SettingsItem::Spacing => self.spacing.name().to_string(),
+            SettingsItem::Controls => if self.relative_turns {
+                "relative"
+            } else {
+                "absolute"
+            }
+            .to_string(),
+            SettingsItem::Portals => if self.portals { "on" } else { "off" }.to_string(),
+            SettingsItem::ClosingWalls => if self.closing_walls { "on" } else { "off" }.to_string(),
+            SettingsItem::Grid => if self.grid { "on" } else { "off" }.to_string(),
+            SettingsItem::DirectionalHead => {
+                if self.directional_head { "on" } else { "off" }.to_string()
+            }
+            SettingsItem::BodyStyle => if self.line_body { "lines" } else { "blocks" }.to_string(),
+            SettingsItem::DeathAnimation => {
+                if self.death_animation { "on" } else { "off" }.to_string()
+            }
+            SettingsItem::PulseApple => if self.pulse_apple { "on" } else { "off" }.to_string(),
+            SettingsItem::Rainbow => if self.rainbow { "on" } else { "off" }.to_string(),
+            SettingsItem::QuickTurns => if self.quick_turns { "on" } else { "off" }.to_string(),
+            SettingsItem::Shrinking => if self.shrinking { "on" } else { "off" }.to_string(),
+            SettingsItem::WallGrace => if self.wall_grace { "on" } else { "off" }.to_string(),
+            SettingsItem::BodyPassthrough => {
+                if self.self_collision { "off" } else { "on" }.to_string()
+            }
+            SettingsItem::Maze => if self.maze { "on" } else { "off" }.to_string(),
+            SettingsItem::Obstacles => self.wall_preset.name().to_string(),
+            SettingsItem::ApplePlacement => self.apple_placement.name().to_string(),
+            SettingsItem::BigApples => if self.big_apples { "on" } else { "off" }.to_string(),
+            SettingsItem::Magnets => if self.magnets { "on" } else { "off" }.to_string(),
+            SettingsItem::RunnerApple => if self.apple_moves { "on" } else { "off" }.to_string(),
+            SettingsItem::AppleHints => if self.apple_hints { "on" } else { "off" }.to_string(),
+            SettingsItem::AppleValue => self.apple_value.to_string(),
+            SettingsItem::CarryOver if self.carry_over == 0 => "off".to_string(),
+            SettingsItem::CarryOver => format!("{}%", self.carry_over),
+            SettingsItem::Shape => self.shape.name().to_string(),
+            SettingsItem::Multiplier => {
+                if self.level_multiplier { "on" } else { "off" }.to_string()
+            }
+            SettingsItem::Fog => match self.fog_radius {
+                Some(radius) => format!("radius {}", radius),
+                None => "off".to_string(),
+            },
+            SettingsItem::BulletTime => match self.bullet_time {
+                Some(percent) => format!("x{}", percent as f32 / 100.0),
+                None => "off".to_string(),
+            },
+            SettingsItem::BulletDistance => match self.bullet_distance {
+                1 => "1 cell".to_string(),
+                n => format!("{} cells", n),
+            },
+        }
+    }
+
+    /// Key/value pairs describing these settings, as stored in replay files
+    pub fn to_kv(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("difficulty", self.difficulty.name().to_string()),
+            ("wrap", self.wrap.name().to_string()),
+            ("speed", self.base_tick_ms.to_string()),
+            ("smooth", self.smooth_speed.to_string()),
+            ("poison", self.poison.to_string()),
+            ("growth", self.growth_per_apple.to_string()),
+            ("apple_count", self.apple_count.to_string()),
+            ("length", self.start_length.to_string()),
+            ("random_start", self.random_start.to_string()),
+            (
+                "theme",
+                if self.random_theme {
+                    RANDOM_THEME.to_string()
+                } else {
+                    THEMES[self.theme].name.to_string()
+                },
+            ),
+            ("versus", self.versus.to_string()),
+            ("header", self.score_display.name().to_string()),
+            ("spacing", self.spacing.name().to_string()),
+            (
+                "controls",
+                if self.relative_turns {
+                    "relative"
+                } else {
+                    "absolute"
+                }
+                .to_string(),
+            ),
+            ("portals", self.portals.to_string()),
+            ("zen", self.zen.to_string()),
+            ("closing_walls", self.closing_walls.to_string()),
+            ("grid", self.grid.to_string()),
+            ("arrows", self.directional_head.to_string()),
+            ("lines", self.line_body.to_string()),
+            ("death_animation", self.death_animation.to_string()),
+            ("pulse_apple", self.pulse_apple.to_string()),
+            ("rainbow", self.rainbow.to_string()),
+            ("quick_turns", self.quick_turns.to_string()),
+            ("shrinking", self.shrinking.to_string()),
+            ("wall_grace", self.wall_grace.to_string()),
+            ("self_collision", self.self_collision.to_string()),
+            ("maze", self.maze.to_string()),
+            ("obstacles", self.wall_preset.name().to_string()),
+            ("apples", self.apple_placement.name().to_string()),
+            ("big_apples", self.big_apples.to_string()),
+            ("magnets", self.magnets.to_string()),
+            ("runner", self.apple_moves.to_string()),
+            ("apple_hints", self.apple_hints.to_string()),
+            ("apple_value", self.apple_value.to_string()),
+            ("multiplier", self.level_multiplier.to_string()),
+            ("lives", self.lives.to_string()),
+            ("carry_over", self.carry_over.to_string()),
+            (
+                "max_length",
+                self.max_length
+                    .map_or_else(|| "off".to_string(), |n| n.to_string()),
+            ),
+            ("shape", self.shape.name().to_string()),
+            (
+                "fog",
+                self.fog_radius
+                    .map_or_else(|| "off".to_string(), |r| r.to_string()),
+            ),
+            (
+                "bullet_time",
+                self.bullet_time
+                    .map_or_else(|| "off".to_string(), |p| p.to_string()),
+            ),
+            ("bullet_distance", self.bullet_distance.to_string()),
+        ]
+    }
+
+    /// Applies one key/value pair written by [`Settings::to_kv`]
+    pub fn set_kv(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", key, value);
+        match key {
+            "difficulty" => self.difficulty = Difficulty::from_name(value).ok_or_else(invalid)?,
+            "wrap" => self.wrap = WrapMode::from_name(value).ok_or_else(invalid)?,
+            "speed" => self.base_tick_ms = value.parse().map_err(|_| invalid())?,
+            "smooth" => self.smooth_speed = value.parse().map_err(|_| invalid())?,
+            "poison" => self.poison = value.parse().map_err(|_| invalid())?,
+            "growth" => {
+                self.growth_per_apple = value.parse().ok().filter(|&g| g > 0).ok_or_else(invalid)?
+            }
+            "apple_count" => {
+                self.apple_count = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?
+            }
+            "length" => {
+                self.start_length = value.parse().ok().filter(|&l| l > 0).ok_or_else(invalid)?
+            }
+            "random_start" => self.random_start = value.parse().map_err(|_| invalid())?,
+            "theme" if value.eq_ignore_ascii_case(RANDOM_THEME) => self.random_theme = true,
+            "theme" => {
+                self.theme = theme_by_name(value).ok_or_else(invalid)?;
+                self.random_theme = false;
+            }
+            "versus" => self.versus = value.parse().map_err(|_| invalid())?,
+            "spacing" => self.spacing = Spacing::from_name(value).ok_or_else(invalid)?,
+            "header" => self.score_display = ScoreDisplay::from_name(value).ok_or_else(invalid)?,
+            "controls" => {
+                self.relative_turns = match value {
+                    "relative" => true,
+                    "absolute" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "portals" => self.portals = value.parse().map_err(|_| invalid())?,
+            "zen" => self.zen = value.parse().map_err(|_| invalid())?,
+            "closing_walls" => self.closing_walls = value.parse().map_err(|_| invalid())?,
+            "grid" => self.grid = value.parse().map_err(|_| invalid())?,
+            "arrows" => self.directional_head = value.parse().map_err(|_| invalid())?,
+            "lines" => self.line_body = value.parse().map_err(|_| invalid())?,
+            "death_animation" => self.death_animation = value.parse().map_err(|_| invalid())?,
+            "pulse_apple" => self.pulse_apple = value.parse().map_err(|_| invalid())?,
+            "rainbow" => self.rainbow = value.parse().map_err(|_| invalid())?,
+            "quick_turns" => self.quick_turns = value.parse().map_err(|_| invalid())?,
+            "shrinking" => self.shrinking = value.parse().map_err(|_| invalid())?,
+            "wall_grace" => self.wall_grace = value.parse().map_err(|_| invalid())?,
+            "self_collision" => self.self_collision = value.parse().map_err(|_| invalid())?,
+            "maze" => self.maze = value.parse().map_err(|_| invalid())?,
+            "obstacles" => self.wall_preset = WallPreset::from_name(value).ok_or_else(invalid)?,
+            "apples" => {
+                self.apple_placement = ApplePlacement::from_name(value).ok_or_else(invalid)?
+            }
+            "big_apples" => self.big_apples = value.parse().map_err(|_| invalid())?,
+            "magnets" => self.magnets = value.parse().map_err(|_| invalid())?,
+            "runner" => self.apple_moves = value.parse().map_err(|_| invalid())?,
+            "apple_hints" => self.apple_hints = value.parse().map_err(|_| invalid())?,
+            "apple_value" => {
+                self.apple_value = value.parse().ok().filter(|&v| v > 0).ok_or_else(invalid)?
+            }
+            "multiplier" => self.level_multiplier = value.parse().map_err(|_| invalid())?,
+            "carry_over" => {
+                self.carry_over = value
+                    .parse()
+                    .ok()
+                    .filter(|&c| c <= MAX_CARRY_OVER)
+                    .ok_or_else(invalid)?
+            }
+            "lives" => self.lives = value.parse().ok().filter(|&l| l > 0).ok_or_else(invalid)?,
+            "max_length" => {
+                self.max_length = match value {
+                    "off" => None,
+                    _ => Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?),
+                }
+            }
+            "shape" => self.shape = BoardShape::from_name(value).ok_or_else(invalid)?,
+            "fog" => {
+                self.fog_radius = match value {
+                    "off" => None,
+                    _ => Some(value.parse().ok().filter(|&r| r > 0).ok_or_else(invalid)?),
+                }
+            }
+            "bullet_time" => {
+                self.bullet_time = match value {
+                    "off" => None,
+                    _ => Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|p| (100..=MAX_BULLET_TIME).contains(p))
+                            .ok_or_else(invalid)?,
+                    ),
+                }
+            }
+            "bullet_distance" => {
+                self.bullet_distance = value
+                    .parse()
+                    .ok()
+                    .filter(|d| (1..=MAX_BULLET_DISTANCE).contains(d))
+                    .ok_or_else(invalid)?
+            }
+            _ => return Err(format!("unknown setting: {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Changes a settings row; `forward` moves to the next value
+    pub fn adjust(&mut self, item: SettingsItem, forward: bool) {
+        match item {
+            SettingsItem::Difficulty => self.set_difficulty(self.difficulty.cycle(forward)),
+            SettingsItem::WrapWalls => self.wrap = self.wrap.cycle(forward),
+            SettingsItem::StartSpeed => {
+                self.base_tick_ms = if forward {
+                    self.base_tick_ms.saturating_sub(20).max(60)
+                } else {
+                    (self.base_tick_ms + 20).min(300)
+                };
+            }
+            SettingsItem::Acceleration => self.smooth_speed = !self.smooth_speed,
+            SettingsItem::Poison => self.poison = !self.poison,
+            SettingsItem::Growth => {
+                self.growth_per_apple = if forward {
+                    (self.growth_per_apple + 1).min(9)
+                } else {
+                    self.growth_per_apple.saturating_sub(1).max(1)
+                };
+            }
+            SettingsItem::StartLength => {
+                self.start_length = if forward {
+                    (self.start_length + 1).min(MAX_START_LENGTH)
+                } else {
+                    self.start_length.saturating_sub(1).max(1)
+                };
+            }
+            SettingsItem::StartPosition => self.random_start = !self.random_start,
+            SettingsItem::Players => self.versus = !self.versus,
+            SettingsItem::ScoreDisplay => self.score_display = self.score_display.cycle(forward),
+            SettingsItem::Spacing => self.spacing = self.spacing.cycle(forward),
+            SettingsItem::Controls => self.relative_turns = !self.relative_turns,
+            SettingsItem::Portals => self.portals = !self.portals,
+            SettingsItem::ClosingWalls => self.closing_walls = !self.closing_walls,
+            SettingsItem::Grid => self.grid = !self.grid,
+            SettingsItem::DirectionalHead => self.directional_head = !self.directional_head,
+            SettingsItem::BodyStyle => self.line_body = !self.line_body,
+            SettingsItem::DeathAnimation => self.death_animation = !self.death_animation,
+            SettingsItem::PulseApple => self.pulse_apple = !self.pulse_apple,
+            SettingsItem::Rainbow => self.rainbow = !self.rainbow,
+            SettingsItem::QuickTurns => self.quick_turns = !self.quick_turns,
+            SettingsItem::Shrinking => self.shrinking = !self.shrinking,
+            SettingsItem::WallGrace => self.wall_grace = !self.wall_grace,
+            SettingsItem::BodyPassthrough => self.self_collision = !self.self_collision,
+            SettingsItem::Maze => self.maze = !self.maze,
+            SettingsItem::Obstacles => self.wall_preset = self.wall_preset.cycle(forward),
+            SettingsItem::ApplePlacement => {
+                self.apple_placement = self.apple_placement.cycle(forward)
+            }
+            SettingsItem::BigApples => self.big_apples = !self.big_apples,
+            SettingsItem::Magnets => self.magnets = !self.magnets,
+            SettingsItem::RunnerApple => self.apple_moves = !self.apple_moves,
+            SettingsItem::AppleHints => self.apple_hints = !self.apple_hints,
+            SettingsItem::AppleValue => {
+                self.apple_value = if forward {
+                    (self.apple_value + 1).min(MAX_APPLE_VALUE)
+                } else {
+                    self.apple_value.saturating_sub(1).max(1)
+                };
+            }
+            SettingsItem::Multiplier => self.level_multiplier = !self.level_multiplier,
+            SettingsItem::CarryOver => {
+                self.carry_over = if forward {
+                    (self.carry_over + CARRY_OVER_STEP).min(MAX_CARRY_OVER)
+                } else {
+                    self.carry_over.saturating_sub(CARRY_OVER_STEP)
+                };
+            }
+            SettingsItem::Shape => self.shape = self.shape.cycle(forward),
+            // Off sits below the smallest radius
+            SettingsItem::Fog => {
+                self.fog_radius = match (self.fog_radius, forward) {
+                    (None, true) => Some(1),
+                    (None, false) => None,
+                    (Some(1), false) => None,
+                    (Some(r), true) => Some((r + 1).min(MAX_FOG_RADIUS)),
+                    (Some(r), false) => Some(r - 1),
+                }
+            }
+            // Off sits below the mildest slowdown
+            SettingsItem::BulletTime => {
+                let mildest = 100 + BULLET_TIME_STEP;
+                self.bullet_time = match (self.bullet_time, forward) {
+                    (None, true) => Some(mildest),
+                    (None, false) => None,
+                    (Some(p), false) if p <= mildest => None,
+                    (Some(p), true) => Some((p + BULLET_TIME_STEP).min(MAX_BULLET_TIME)),
+                    (Some(p), false) => Some(p - BULLET_TIME_STEP),
+                }
+            }
+            SettingsItem::BulletDistance => {
+                self.bullet_distance = if forward {
+                    (self.bullet_distance + 1).min(MAX_BULLET_DISTANCE)
+                } else {
+                    self.bullet_distance.saturating_sub(1).max(1)
+                };
+            }
+            // "Surprise me" sits after the last theme
+            SettingsItem::Theme => {
+                let last = THEMES.len() - 1;
+                match (self.random_theme, forward) {
+                    (true, true) => {
+                        self.random_theme = false;
+                        self.theme = 0;
+                    }
+                    (true, false) => {
+                        self.random_theme = false;
+                        self.theme = last;
+                    }
+                    (false, true) if self.theme == last => self.random_theme = true,
+                    (false, false) if self.theme == 0 => self.random_theme = true,
+                    (false, true) => self.theme += 1,
+                    (false, false) => self.theme -= 1,
+                }
+            }
+        }
+    }
+}
+
+/// Something that happened during a game step, for the UI to react to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A snake moved one cell
+    Moved { player: usize },
+    /// A snake ate an apple or golden apple
+    AppleEaten { player: usize },
+    /// The score reached a new level
+    LevelUp(u32),
+    /// Wall grace forgave a snake's wall hit
+    WallGrace { player: usize },
+    /// The campaign level's apple target was reached
+    LevelCleared,
+    /// A crash cost a life and the snake was put back on the board
+    LifeLost { player: usize },
+    /// The game ended
+    GameOver,
+}
+
+/// Built-in campaign maps, in play order
+pub const CAMPAIGN: &str = include_str!("../assets/campaign.txt");
+
+/// A fixed-size map with its own walls: a campaign level with an apple
+/// target, or a board loaded with `--level`
+pub struct Level {
+    pub name: String,
+    pub target: Option<u32>,
+    pub width: u16,
+    pub height: u16,
+    pub walls: Vec<Point>,
+    /// Where the snake's head starts, instead of the middle of the board
+    pub start: Option<Point>,
+    /// Where the first apple goes, instead of a random cell
+    pub apple: Option<Point>,
+}
+
+impl Level {
+    /// Parses levels written as a `level <target> <name>` line followed by
+    /// map rows, `#` for wall and `.` for open; `;` starts a comment line
+    pub fn parse_all(text: &str) -> Result<Vec<Level>, String> {
+        let mut levels: Vec<Level> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let invalid = |what: &str| format!("line {}: {}", n + 1, what);
+            if let Some(rest) = line.strip_prefix("level ") {
+                let (target, name) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| invalid("missing name"))?;
+                levels.push(Level {
+                    name: name.trim().to_string(),
+                    target: Some(target.parse().map_err(|_| invalid("invalid target"))?),
+                    width: 0,
+                    height: 0,
+                    walls: Vec::new(),
+                    start: None,
+                    apple: None,
+                });
+                continue;
+            }
+            let level = levels
+                .last_mut()
+                .ok_or_else(|| invalid("map row before any level"))?;
+            let width = line.chars().count() as u16;
+            if level.height > 0 && width != level.width {
+                return Err(invalid("rows differ in width"));
+            }
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '#' => level.walls.push(Point {
+                        x: x as u16,
+                        y: level.height,
+                    }),
+                    '.' => {}
+                    _ => return Err(invalid("unknown map character")),
+                }
+            }
+            level.width = width;
+            level.height += 1;
+        }
+        if let Some(level) = levels.iter().find(|l| l.width < 10 || l.height < 5) {
+            return Err(format!("level {} is smaller than 10x5", level.name));
+        }
+        Ok(levels)
+    }
+
+    /// The built-in campaign
+    pub fn campaign() -> Vec<Level> {
+        Self::parse_all(CAMPAIGN).expect("built-in campaign is valid")
+    }
+
+    /// Parses a single hand-drawn board: `#` for wall, space for open, `S`
+    /// for the snake's start and `A` for the first apple
+    ///
+    /// The board is as wide as its longest row; shorter rows are open to the
+    /// right, so editors that strip trailing spaces do no harm.
+    pub fn parse_board(name: &str, text: &str) -> Result<Level, String> {
+        let rows: Vec<&str> = text.lines().map(str::trim_end).collect();
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let mut level = Level {
+            name: name.to_string(),
+            target: None,
+            width: width as u16,
+            height: rows.len() as u16,
+            walls: Vec::new(),
+            start: None,
+            apple: None,
+        };
+        if level.width < 10 || level.height < 5 {
+            return Err(format!(
+                "board is {}x{}, smaller than 10x5",
+                level.width, level.height
+            ));
+        }
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let p = Point {
+                    x: x as u16,
+                    y: y as u16,
+                };
+                let at = || format!("line {}, column {}", y + 1, x + 1);
+                match c {
+                    '#' => level.walls.push(p),
+                    ' ' => {}
+                    'S' if level.start.is_some() => {
+                        return Err(format!("{}: more than one snake start (S)", at()));
+                    }
+                    'S' => level.start = Some(p),
+                    'A' if level.apple.is_some() => {
+                        return Err(format!("{}: more than one apple (A)", at()));
+                    }
+                    'A' => level.apple = Some(p),
+                    _ => return Err(format!("{}: unknown character {:?}", at(), c)),
+                }
+            }
+        }
+        if level.start.is_none() {
+            return Err("no snake start (S) on the board".to_string());
+        }
+        Ok(level)
+    }
+
+    /// Reads a board file written for [`Level::parse_board`], named after
+    /// the file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let name = path.file_stem().map_or_else(
+            || "Custom".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        );
+        Self::parse_board(&name, &text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+/// A recorded run: everything needed to reproduce it tick for tick
+pub struct Replay {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub settings: Settings,
+    pub turns: Vec<(u64, usize, DirectionEnum)>,
+    pub resizes: Vec<(u64, u16, u16)>,
+    /// Combo multiplier of each apple eaten on a combo, by tick
+    pub combos: Vec<(u64, u32)>,
+    /// Final score and tick count; zero in replays saved before they were
+    /// recorded
+    pub score: u32,
+    pub ticks: u64,
+}
+
+impl Replay {
+    /// Serializes the replay as a small line-based text format
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("snake-replay 1\n");
+        out += &format!("seed {}\n", self.seed);
+        out += &format!("size {} {}\n", self.width, self.height);
+        for (key, value) in self.settings.to_kv() {
+            out += &format!("set {} {}\n", key, value);
+        }
+        for (tick, player, d) in &self.turns {
+            out += &format!("turn {} {} {}\n", tick, player, d.name());
+        }
+        for (tick, w, h) in &self.resizes {
+            out += &format!("resize {} {} {}\n", tick, w, h);
+        }
+        for (tick, combo) in &self.combos {
+            out += &format!("combo {} {}\n", tick, combo);
+        }
+        out += &format!("result {} {}\n", self.score, self.ticks);
+        out
+    }
+
+    /// Parses text written by [`Replay::to_text`]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some("snake-replay 1") {
+            return Err("not a snake replay file".to_string());
+        }
+        let mut replay = Replay {
+            seed: 0,
+            width: 0,
+            height: 0,
+            settings: Settings::default(),
+            turns: Vec::new(),
+            resizes: Vec::new(),
+            combos: Vec::new(),
+            score: 0,
+            ticks: 0,
+        };
+        for (i, line) in lines.enumerate() {
+            let bad = || format!("replay line {}: malformed entry: {}", i + 2, line);
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                [] => {}
+                ["seed", seed] => replay.seed = seed.parse().map_err(|_| bad())?,
+                ["size", w, h] => {
+                    replay.width = w.parse().map_err(|_| bad())?;
+                    replay.height = h.parse().map_err(|_| bad())?;
+                }
+                ["set", key, value @ ..] => replay.settings.set_kv(key, &value.join(" "))?,
+                ["turn", tick, player, d] => replay.turns.push((
+                    tick.parse().map_err(|_| bad())?,
+                    player.parse().map_err(|_| bad())?,
+                    DirectionEnum::from_name(d).ok_or_else(bad)?,
+                )),
+                ["resize", tick, w, h] => replay.resizes.push((
+                    tick.parse().map_err(|_| bad())?,
+                    w.parse().map_err(|_| bad())?,
+                    h.parse().map_err(|_| bad())?,
+                )),
+                ["combo", tick, combo] => replay.combos.push((
+                    tick.parse().map_err(|_| bad())?,
+                    combo.parse().map_err(|_| bad())?,
+                )),
+                ["result", score, ticks] => {
+                    replay.score = score.parse().map_err(|_| bad())?;
+                    replay.ticks = ticks.parse().map_err(|_| bad())?;
+                }
+                _ => return Err(bad()),
+            }
+        }
+        if replay.width == 0 || replay.height == 0 {
+            return Err("replay is missing the board size".to_string());
+        }
+        Ok(replay)
+    }
+
+    /// Reads and parses a replay file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        Self::parse(&text)
+    }
+
+    /// Writes the replay file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_text())
+    }
+}
+
+/// A recorded run played back alongside a live one, for racing against
+///
+/// It moves one tick for each live tick and never touches the live board.
+pub struct Ghost {
+    replay: Replay,
+    game: Game,
+    /// Set on the first live tick past the end of the recording
+    ended: bool,
+}
+
+impl Ghost {
+    pub fn new(replay: Replay) -> Self {
+        let game = Game::for_replay(&replay);
+        Ghost {
+            replay,
+            game,
+            ended: false,
+        }
+    }
+
+    /// Advances one tick, feeding in the recorded turns and resizes
+    pub fn step(&mut self) {
+        if self.finished() {
+            return;
+        }
+        let tick = self.game.ticks;
+        if self.replay.ticks > 0 && tick >= self.replay.ticks {
+            self.ended = true;
+            return;
+        }
+        for &(_, w, h) in self.replay.resizes.iter().filter(|r| r.0 == tick) {
+            self.game.resize(w, h);
+        }
+        for &(_, player, d) in self.replay.turns.iter().filter(|t| t.0 == tick) {
+            if let Some(snake) = self.game.snakes.get_mut(player) {
+                snake.dir_queue.push_back(d);
+            }
+        }
+        self.game.step();
+        self.game.events.clear();
+    }
+
+    /// True once the recorded run has ended; a live run that lasts longer
+    /// carries on alone
+    pub fn finished(&self) -> bool {
+        self.game.game_over || self.ended
+    }
+
+    /// Ticks the ghost has run and whether it has ended, for [`Ghost::rewind`]
+    pub fn progress(&self) -> (u64, bool) {
+        (self.game.ticks, self.ended)
+    }
+
+    /// Replays the recording from the start up to `ticks`, putting the ghost
+    /// back where [`Ghost::progress`] found it
+    pub fn rewind(&mut self, ticks: u64, ended: bool) {
+        self.game = Game::for_replay(&self.replay);
+        self.ended = false;
+        while self.game.ticks < ticks && !self.finished() {
+            self.step();
+        }
+        self.ended = ended;
+    }
+
+    /// Cells of the ghost snake, head first, while it's still running
+    pub fn body(&self) -> Option<&[Point]> {
+        (!self.finished()).then(|| self.game.snakes[0].body.as_slice())
+    }
+}
+
+/// Directory for saved game data (`$XDG_CONFIG_HOME/snake` or `~/.config/snake`)
+pub fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("snake"))
+}
+
+/// Where the all-time best score is kept (`~/.snake_highscore`)
+pub fn high_score_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".snake_highscore"))
+}
+
+/// Parses a high score file: a version line, then the score
+///
+/// A bare score with no version line, as files from before the header were
+/// written, still reads; saving over it backs it up first.
+pub fn parse_high_score(text: &str) -> Result<u32, String> {
+    let line = match HIGH_SCORE_FILE.body(text) {
+        Ok(mut lines) => lines.next().unwrap_or_default(),
+        Err(_) if format_version(text, HIGH_SCORE_FILE.kind).is_none() => {
+            text.lines().next().unwrap_or_default()
+        }
+        Err(err) => return Err(err),
+    };
+    line.trim()
+        .parse()
+        .map_err(|_| format!("malformed high score: {}", line))
+}
+
+/// Reads the high score at `path`; a missing or malformed file counts as 0
+pub fn read_high_score(path: &Path) -> u32 {
+    HIGH_SCORE_FILE.read(path, parse_high_score).unwrap_or(0)
+}
+
+/// Writes `score` to `path` if it beats the one saved there, creating the
+/// directory if needed; returns true when it was written
+pub fn write_high_score(path: &Path, score: u32) -> io::Result<bool> {
+    if score <= read_high_score(path) {
+        return Ok(false);
+    }
+    let text = format!("{}{}\n", HIGH_SCORE_FILE.header(), score);
+    HIGH_SCORE_FILE.write(path, &text, parse_high_score)?;
+    Ok(true)
+}
+
+/// The saved all-time best score, or 0 when there is none
+pub fn load_high_score() -> u32 {
+    high_score_path().map_or(0, |path| read_high_score(&path))
+}
+
+/// Saves `score` as the all-time best if it strictly beats the saved one
+pub fn save_high_score(score: u32) -> io::Result<bool> {
+    match high_score_path() {
+        Some(path) => write_high_score(&path, score),
+        None => Ok(false),
+    }
+}
+
+/// Where the most recent finished run is recorded
+pub fn last_replay_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("last.replay"))
+}
+
+/// Where the best time-attack run for a `limit`-long clock is recorded
+pub fn best_run_path(limit: Duration) -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(format!("best-{}s.replay", limit.as_secs())))
+}
+
+/// Best scores with the initials of whoever set them, highest first
+#[derive(Default)]
+pub struct Leaderboard {
+    pub entries: Vec<(String, u32)>,
+}
+
+impl Leaderboard {
+    /// Serializes the board as a versioned text file, one entry per line
+    pub fn to_text(&self) -> String {
+        let mut out = LEADERBOARD_FILE.header();
+        for (name, score) in &self.entries {
+            out.push_str(&format!("{} {}\n", name, score));
+        }
+        out
+    }
+
+    /// Parses text written by [`Leaderboard::to_text`]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut board = Leaderboard::default();
+        for line in LEADERBOARD_FILE
+            .body(text)?
+            .filter(|l| !l.trim().is_empty())
+        {
+            let bad = || format!("malformed leaderboard entry: {}", line);
+            let (name, score) = line.trim().split_once(' ').ok_or_else(bad)?;
+            if name.chars().count() != INITIALS_LEN {
+                return Err(bad());
+            }
+            board
+                .entries
+                .push((name.to_string(), score.parse().map_err(|_| bad())?));
+        }
+        board.entries.sort_by_key(|e| std::cmp::Reverse(e.1));
+        board.entries.truncate(LEADERBOARD_SIZE);
+        Ok(board)
+    }
+
+    /// Reads the board; a missing or corrupt file starts a fresh one
+    pub fn load(path: &Path) -> Self {
+        LEADERBOARD_FILE.read(path, Self::parse).unwrap_or_default()
+    }
+
+    /// Writes the board file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        LEADERBOARD_FILE.write(path, &self.to_text(), Self::parse)
+    }
+
+    /// True if `score` would earn a place on the board
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < LEADERBOARD_SIZE
+                || self.entries.last().is_some_and(|(_, s)| score > *s))
+    }
+
+    /// Adds an entry below any equal scores, dropping whatever falls off
+    pub fn insert(&mut self, name: String, score: u32) {
+        let rank = self
+            .entries
+            .iter()
+            .position(|(_, s)| score > *s)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, (name, score));
+        self.entries.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+/// The first of `<name>.bak`, `<name>.bak.1`, `<name>.bak.2`... next to
+/// `path` that doesn't exist yet, so older backups are never overwritten
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut n = 0;
+    loop {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(if n == 0 {
+            ".bak".to_string()
+        } else {
+            format!(".bak.{}", n)
+        });
+        let backup = path.with_file_name(name);
+        if !backup.exists() {
+            return backup;
+        }
+        n += 1;
+    }
+}
+
+/// Version number from a `<kind> <version>` first line, if `text` has one
+pub fn format_version(text: &str, kind: &str) -> Option<u32> {
+    text.lines()
+        .next()?
+        .strip_prefix(kind)?
+        .strip_prefix(' ')?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Moves the file at `path` to a free [`backup_path`] unless `current`
+/// accepts it as the current format; a missing file is left alone
+fn back_up_unless(path: &Path, current: impl FnOnce(&str) -> bool) -> io::Result<()> {
+    if !path.exists() || fs::read_to_string(path).is_ok_and(|old| current(&old)) {
+        return Ok(());
+    }
+    fs::rename(path, backup_path(path))
+}
+
+/// A text file kept between sessions, headed by a `<kind> <version>` line
+///
+/// Reading treats a missing or unparsable file as absent. Writing creates
+/// the directory if needed, and a file already there that this version
+/// can't read, say one from a newer release, is moved aside to a
+/// [`backup_path`] rather than clobbered.
+pub struct VersionedFile {
+    pub kind: &'static str,
+    pub version: u32,
+    /// What the file holds, for error messages
+    pub name: &'static str,
+}
+
+impl VersionedFile {
+    /// The header line, newline included
+    pub fn header(&self) -> String {
+        format!("{} {}\n", self.kind, self.version)
+    }
+
+    /// The lines of `text` after its header, if it's this kind and version
+    pub fn body<'a>(&self, text: &'a str) -> Result<std::str::Lines<'a>, String> {
+        match format_version(text, self.kind) {
+            Some(v) if v == self.version => {
+                let mut lines = text.lines();
+                lines.next();
+                Ok(lines)
+            }
+            Some(v) => Err(format!("unsupported {} version {}", self.name, v)),
+            None => Err(format!("not a {} file", self.name)),
+        }
+    }
+
+    /// The file at `path` run through `parse`, if it reads and parses
+    pub fn read<T>(&self, path: &Path, parse: impl FnOnce(&str) -> Result<T, String>) -> Option<T> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| parse(&text).ok())
+    }
+
+    /// Writes `text` to `path`, backing up an old file that isn't this
+    /// version or that `parse` rejects
+    pub fn write<T>(
+        &self,
+        path: &Path,
+        text: &str,
+        parse: impl FnOnce(&str) -> Result<T, String>,
+    ) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        back_up_unless(path, |old| {
+            format_version(old, self.kind) == Some(self.version) && parse(old).is_ok()
+        })?;
+        fs::write(path, text)
+    }
+}
+
+/// The all-time best score file
+pub const HIGH_SCORE_FILE: VersionedFile = VersionedFile {
+    kind: "snake-highscore",
+    version: HIGH_SCORE_VERSION,
+    name: "high score",
+};
+
+/// The leaderboard file
+pub const LEADERBOARD_FILE: VersionedFile = VersionedFile {
+    kind: "snake-leaderboard",
+    version: LEADERBOARD_VERSION,
+    name: "leaderboard",
+};
+
+/// The personal-best splits file
+pub const SPLITS_FILE: VersionedFile = VersionedFile {
+    kind: "snake-splits",
+    version: 1,
+    name: "splits",
+};
+
+/// The saved settings file
+pub const SETTINGS_FILE: VersionedFile = VersionedFile {
+    kind: "snake-settings",
+    version: 1,
+    name: "settings",
+};
+
+/// The daily challenge bests file
+pub const DAILY_FILE: VersionedFile = VersionedFile {
+    kind: "snake-daily",
+    version: 1,
+    name: "daily scores",
+};
+
+/// Where the top-10 leaderboard is stored
+pub fn leaderboard_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("leaderboard"))
+}
+
+/// Scores at which a speedrun to `goal` takes its splits: every
+/// [`SPLIT_EVERY`] points, then the goal itself
+pub fn split_marks(goal: u32) -> Vec<u32> {
+    (1..)
+        .map(|n| n * SPLIT_EVERY)
+        .take_while(|&mark| mark < goal)
+        .chain([goal])
+        .collect()
+}
+
+/// Personal-best speedrun splits, one set per goal score
+#[derive(Default)]
+pub struct BestSplits {
+    pub runs: Vec<(u32, Vec<Duration>)>,
+}
+
+impl BestSplits {
+    /// Serializes the splits as a versioned text file, one goal per line
+    /// followed by its split times in milliseconds
+    pub fn to_text(&self) -> String {
+        let mut out = SPLITS_FILE.header();
+        for (goal, splits) in &self.runs {
+            let times: Vec<String> = splits.iter().map(|d| d.as_millis().to_string()).collect();
+            out.push_str(&format!("{} {}\n", goal, times.join(" ")));
+        }
+        out
+    }
+
+    /// Parses text written by [`BestSplits::to_text`]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut best = BestSplits::default();
+        for line in SPLITS_FILE.body(text)?.filter(|l| !l.trim().is_empty()) {
+            let bad = || format!("malformed splits entry: {}", line);
+            let mut fields = line.split_whitespace().map(|f| f.parse::<u64>());
+            let goal = fields.next().ok_or_else(bad)?.map_err(|_| bad())? as u32;
+            let splits = fields
+                .map(|ms| ms.map(Duration::from_millis))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| bad())?;
+            if splits.len() != split_marks(goal).len() {
+                return Err(bad());
+            }
+            best.runs.push((goal, splits));
+        }
+        Ok(best)
+    }
+
+    /// Reads the splits; a missing or corrupt file starts afresh
+    pub fn load(path: &Path) -> Self {
+        SPLITS_FILE.read(path, Self::parse).unwrap_or_default()
+    }
+
+    /// Writes the splits file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        SPLITS_FILE.write(path, &self.to_text(), Self::parse)
+    }
+
+    /// Best splits for a goal, if it has been finished before
+    pub fn get(&self, goal: u32) -> Option<&[Duration]> {
+        self.runs
+            .iter()
+            .find(|(g, _)| *g == goal)
+            .map(|(_, splits)| splits.as_slice())
+    }
+
+    /// Keeps `splits` if they finish faster than the best for `goal`;
+    /// returns true for a new personal best
+    pub fn record(&mut self, goal: u32, splits: &[Duration]) -> bool {
+        let Some(&finish) = splits.last() else {
+            return false;
+        };
+        if splits.len() != split_marks(goal).len() {
+            return false;
+        }
+        match self.runs.iter_mut().find(|(g, _)| *g == goal) {
+            Some((_, best)) if best.last().is_some_and(|&b| b <= finish) => false,
+            Some((_, best)) => {
+                *best = splits.to_vec();
+                true
+            }
+            None => {
+                self.runs.push((goal, splits.to_vec()));
+                true
+            }
+        }
+    }
+}
+
+/// Settings remembered between sessions, as keys of [`Settings::to_kv`]
+pub const SAVED_SETTINGS: [&str; 1] = ["theme"];
+
+impl Settings {
+    /// The fixed rules every daily challenge is played with: defaults for
+    /// everything but how the game looks and is steered
+    pub fn daily(&self) -> Settings {
+        Settings {
+            theme: self.theme,
+            random_theme: self.random_theme,
+            ascii: self.ascii,
+            square_cells: self.square_cells,
+            cell_width: self.cell_width,
+            ruler: self.ruler,
+            grid: self.grid,
+            directional_head: self.directional_head,
+            rainbow: self.rainbow,
+            line_body: self.line_body,
+            death_animation: self.death_animation,
+            pulse_apple: self.pulse_apple,
+            score_display: self.score_display,
+            spacing: self.spacing,
+            relative_turns: self.relative_turns,
+            ..Settings::default()
+        }
+    }
+
+    /// These settings with the glyph options of `viewer`, which depend on
+    /// the terminal a game is shown on rather than the one it was played on
+    pub fn with_glyphs_of(&self, viewer: &Settings) -> Settings {
+        Settings {
+            ascii: viewer.ascii,
+            square_cells: viewer.square_cells,
+            cell_width: viewer.cell_width,
+            ruler: viewer.ruler,
+            ..self.clone()
+        }
+    }
+
+    /// The [`SAVED_SETTINGS`] as a versioned text file, one `key value`
+    /// per line
+    pub fn saved_text(&self) -> String {
+        let mut out = SETTINGS_FILE.header();
+        for (key, value) in self.to_kv() {
+            if SAVED_SETTINGS.contains(&key) {
+                out += &format!("{} {}\n", key, value);
+            }
+        }
+        out
+    }
+
+    /// Defaults with the saved values from text written by
+    /// [`Settings::saved_text`] applied on top
+    pub fn parse_saved(text: &str) -> Result<Self, String> {
+        let mut settings = Settings::default();
+        for line in SETTINGS_FILE.body(text)?.filter(|l| !l.trim().is_empty()) {
+            let (key, value) = line
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| format!("malformed setting: {}", line))?;
+            settings.set_kv(key, value)?;
+        }
+        Ok(settings)
+    }
+
+    /// Reads the saved settings; a missing or corrupt file gives defaults
+    pub fn load_saved(path: &Path) -> Self {
+        SETTINGS_FILE
+            .read(path, Self::parse_saved)
+            .unwrap_or_default()
+    }
+
+    /// Writes the settings file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        SETTINGS_FILE.write(path, &self.saved_text(), Self::parse_saved)
+    }
+}
+
+/// Where settings kept between sessions are stored
+pub fn settings_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("settings"))
+}
+
+/// Best daily challenge score for each day played
+#[derive(Default)]
+pub struct DailyBests {
+    pub days: Vec<(String, u32)>,
+}
+
+impl DailyBests {
+    /// Serializes the scores as a versioned text file, one `date score`
+    /// per line
+    pub fn to_text(&self) -> String {
+        let mut out = DAILY_FILE.header();
+        for (date, score) in &self.days {
+            out.push_str(&format!("{} {}\n", date, score));
+        }
+        out
+    }
+
+    /// Parses text written by [`DailyBests::to_text`]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut best = DailyBests::default();
+        for line in DAILY_FILE.body(text)?.filter(|l| !l.trim().is_empty()) {
+            let bad = || format!("malformed daily score: {}", line);
+            let (date, score) = line.trim().split_once(' ').ok_or_else(bad)?;
+            best.days
+                .push((date.to_string(), score.parse().map_err(|_| bad())?));
+        }
+        Ok(best)
+    }
+
+    /// Reads the scores; a missing or corrupt file starts afresh
+    pub fn load(path: &Path) -> Self {
+        DAILY_FILE.read(path, Self::parse).unwrap_or_default()
+    }
+
+    /// Writes the scores file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        DAILY_FILE.write(path, &self.to_text(), Self::parse)
+    }
+
+    /// Best score for a day, if it has been played
+    pub fn get(&self, date: &str) -> Option<u32> {
+        self.days.iter().find(|(d, _)| d == date).map(|&(_, s)| s)
+    }
+
+    /// Keeps `score` if it beats the best for `date`; returns true for a
+    /// new best
+    pub fn record(&mut self, date: &str, score: u32) -> bool {
+        match self.days.iter_mut().find(|(d, _)| d == date) {
+            Some((_, best)) if *best >= score => false,
+            Some((_, best)) => {
+                *best = score;
+                true
+            }
+            None => {
+                self.days.push((date.to_string(), score));
+                true
+            }
+        }
+    }
+}
+
+/// Where daily challenge bests are kept
+pub fn daily_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("daily"))
+}
+
+/// Year, month and day of the date `days` after 1970-01-01
+///
+/// Howard Hinnant's `civil_from_days`, counting in 400-year eras.
+pub fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Today's UTC date as `YYYY-MM-DD`
+pub fn utc_date() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Seed shared by everyone playing the daily challenge on `date`
+///
+/// FNV-1a over the date text, so it's the same on every platform and build.
+pub fn daily_seed(date: &str) -> u64 {
+    date.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Where personal-best speedrun splits are kept
+pub fn splits_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("splits"))
+}
+
+/// Board state saved before a step, so the step can be undone
+pub struct Snapshot {
+    pub snakes: Vec<Snake>,
+    pub apple: Point,
+    pub apples: Vec<Point>,
+    pub next_apple: Option<Point>,
+    pub next_apple_rng: Option<StdRng>,
+    pub big_apple: Option<[Point; 4]>,
+    pub special: Option<SpecialApple>,
+    pub poison: Option<Point>,
+    pub magnet: Option<Point>,
+    pub magnet_until: Option<u64>,
+    pub portals: Option<(Point, Point)>,
+    pub walls: HashSet<Point>,
+    pub combo: u32,
+    pub last_eat: Option<Instant>,
+    pub bullet_ticks: u16,
+    pub splits: Vec<Duration>,
+    pub trail: Option<VecDeque<(Point, u8)>>,
+    /// The ghost's [`Ghost::progress`]
+    pub ghost: Option<(u64, bool)>,
+    pub inset: u16,
+    pub score: u32,
+    pub apples_eaten: u32,
+    pub level: u32,
+    pub lives: u32,
+    pub ticks: u64,
+    pub rng: StdRng,
+}
+
+/// One player's snake and its pending input
+#[derive(Clone)]
+pub struct Snake {
+    pub body: Vec<Point>,
+    pub dir: DirectionEnum,
+    pub dir_queue: VecDeque<DirectionEnum>,
+    pub pending_growth: u16,
+    pub score: u32,
+    pub alive: bool,
+    /// Set once a wall hit has been forgiven; eating an apple clears it
+    pub grace_used: bool,
+}
+
+impl Snake {
+    /// Lays out `length` segments trailing behind `head`
+    ///
+    /// The caller must make sure they fit on the board.
+    pub fn new(head: Point, dir: DirectionEnum, length: u16) -> Self {
+        let body = (0..length)
+            .map(|i| match dir {
+                DirectionEnum::Right => Point {
+                    x: head.x.saturating_sub(i),
+                    y: head.y,
+                },
+                DirectionEnum::Left => Point {
+                    x: head.x + i,
+                    y: head.y,
+                },
+                DirectionEnum::Down => Point {
+                    x: head.x,
+                    y: head.y.saturating_sub(i),
+                },
+                DirectionEnum::Up => Point {
+                    x: head.x,
+                    y: head.y + i,
+                },
+            })
+            .collect();
+        Self {
+            body,
+            dir,
+            dir_queue: VecDeque::new(),
+            pending_growth: 0,
+            score: 0,
+            alive: true,
+            grace_used: false,
+        }
+    }
+
+    /// Current head position
+    pub fn head(&self) -> Point {
+        self.body[0]
+    }
+
+    /// Changes snake direction (no reverse allowed)
+    ///
+    /// Turns are queued so quick successive presses each get their own tick
+    pub fn set_direction(&mut self, d: DirectionEnum) {
+        let last = self.dir_queue.back().copied().unwrap_or(self.dir);
+        if d == last || d.is_reverse_of(last) || self.dir_queue.len() >= INPUT_BUFFER_SIZE {
+            return;
+        }
+        self.dir_queue.push_back(d);
+    }
+
+    /// Applies the next buffered turn, re-checking it against the live direction
+    pub fn apply_queued_turn(&mut self) {
+        while let Some(d) = self.dir_queue.pop_front() {
+            if !d.is_reverse_of(self.dir) {
+                self.dir = d;
+                break;
+            }
+        }
+    }
+}
+
+/// Main game state
+pub struct Game {
+    /// Settings the game was started with
+    pub settings: Settings,
+    pub snakes: Vec<Snake>,
+    pub occupied: HashSet<Point>,
+    pub apple: Point,
+    /// Extra apples alongside `apple`, topped up to `apple_count - 1`
+    pub apples: Vec<Point>,
+    pub special: Option<SpecialApple>,
+    pub apples_eaten: u32,
+    pub poison: Option<Point>,
+    /// Where the next apple will spawn, shown as a hint when `apple_hints`
+    /// is on
+    pub next_apple: Option<Point>,
+    /// The RNG as it was left by drawing `next_apple`, taken up again when
+    /// the hint is used
+    pub next_apple_rng: Option<StdRng>,
+    /// Uneaten magnet power-up
+    pub magnet: Option<Point>,
+    /// Tick the apple stops being pulled toward the snake
+    pub magnet_until: Option<u64>,
+    pub portals: Option<(Point, Point)>,
+    pub walls: HashSet<Point>,
+    /// Cells reachable from the head, kept for maze apple placement until
+    /// the walls or arena change
+    maze_region: Option<HashSet<Point>>,
+    /// The 2x2 block the apple covers when it's a big apple; `apple` is then
+    /// its top-left cell
+    pub big_apple: Option<[Point; 4]>,
+    /// Lives left, counting the current one
+    pub lives: u32,
+    /// Apples eaten in quick succession, counting the latest one
+    pub combo: u32,
+    pub last_eat: Option<Instant>,
+    /// Apples eaten quickly build combos; off unless turned on for live
+    /// play, since the wall-clock timing can't be reproduced headless
+    pub combos_enabled: bool,
+    /// Combos scored this game, by tick, for the replay
+    pub combo_log: Vec<(u64, u32)>,
+    /// Recorded combos a replay scores with instead of timing its own
+    pub replayed_combos: Option<Vec<(u64, u32)>>,
+    /// Score that clears the current campaign level
+    pub target: Option<u32>,
+    /// Speedrun goal score; reaching it sets `target`'s cleared flag too
+    pub speedrun_goal: Option<u32>,
+    /// Score that wins the game outright, from `--goal`
+    pub target_score: Option<u32>,
+    /// The target score was reached; the game is over and won
+    pub won: bool,
+    /// Run time at each of [`split_marks`] passed so far
+    pub splits: Vec<Duration>,
+    /// Personal-best splits for the same goal, to compare against
+    pub best_splits: Vec<Duration>,
+    /// Run time frozen when the run finished or crashed
+    pub finish_time: Option<Duration>,
+    pub cleared: bool,
+    pub inset: u16,
+    /// Index into [`THEMES`] this game is drawn with
+    pub theme: usize,
+    pub random_theme: bool,
+    /// Ticks in a row bullet time has slowed so far
+    pub bullet_ticks: u16,
+    pub trail: Option<VecDeque<(Point, u8)>>,
+    /// Best earlier run, replayed dimly in step with this one
+    pub ghost: Option<Box<Ghost>>,
+    /// Date of the daily challenge this game is, from `--daily`
+    pub daily: Option<String>,
+    /// Best score saved from earlier sessions, shown in the header
+    pub high_score: u32,
+    pub rng: StdRng,
+    pub seed: u64,
+    pub ticks: u64,
+    pub turns: Vec<(u64, usize, DirectionEnum)>,
+    pub resizes: Vec<(u64, u16, u16)>,
+    pub start_size: (u16, u16),
+    pub playback: bool,
+    pub score: u32,
+    pub width: u16,
+    pub height: u16,
+    pub game_over: bool,
+    pub paused: bool,
+    /// Controls overlay is open; the game stays paused underneath
+    pub show_help: bool,
+    pub winner: Option<usize>,
+    pub countdown: Option<u8>,
+    pub time_limit: Option<Duration>,
+    /// Permadeath: the run can't be restarted, only quit
+    pub hardcore: bool,
+    pub start: Instant,
+    pub time_up: bool,
+    pub boost_until: Option<Instant>,
+    pub level_banner_until: Option<Instant>,
+    pub grace_warning_until: Option<Instant>,
+    /// Segments of crashed snakes hidden from the head, while the death
+    /// animation plays
+    pub vanished: usize,
+    pub level: u32,
+    pub base_tick_ms: u64,
+    /// Practice mode: crashes are ignored while this is on
+    pub invincible: bool,
+    /// Set once practice mode is used; the run no longer counts for scores
+    pub practice: bool,
+    /// Points this game started with, carried from the one before
+    pub carried: u32,
+    pub wrap: WrapMode,
+    pub start_length: usize,
+    pub autopilot: bool,
+    /// Events since the last [`Game::drain_events`]
+    pub events: Vec<GameEvent>,
+    /// States before the most recent steps, oldest first; only kept once
+    /// [`Game::keep_history`] turns it on
+    pub history: Option<VecDeque<Snapshot>>,
+}
+
+impl Game {
+    /// Initializes a new game session sized for a terminal of `width` x
+    /// `height` characters
+    pub fn new(width: u16, height: u16, settings: &Settings) -> Self {
+        Self::with_seed(width, height, settings, rand::random())
+    }
+
+    /// Initializes a game whose apple placement is driven by `seed`, sized
+    /// for a terminal of `width` x `height` characters
+    pub fn with_seed(width: u16, height: u16, settings: &Settings, seed: u64) -> Self {
+        let (width, height) = Self::board_size(width, height, settings);
+        Self::from_board(width, height, settings, seed)
+    }
+
+    /// Initializes a game on the board [`Game::sized_board`] picks for the
+    /// requested sides and a terminal of `columns` x `rows`
+    pub fn with_size(
+        width: Option<u16>,
+        height: Option<u16>,
+        columns: u16,
+        rows: u16,
+        settings: &Settings,
+        seed: u64,
+    ) -> Self {
+        let (width, height) = Self::sized_board(width, height, columns, rows, settings);
+        Self::from_board(width, height, settings, seed)
+    }
+
+    /// Initializes a game on a board of exactly `width` x `height` cells
+    pub fn from_board(width: u16, height: u16, settings: &Settings, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        // Drawn from the game's own RNG so a replay's seed brings back the
+        // same theme
+        let theme = if settings.zen {
+            ZEN_THEME
+        } else if settings.random_theme {
+            rng.gen_range(0..THEMES.len())
+        } else {
+            settings.theme
+        };
+
+        // One snake in the middle, or two facing each other in versus mode;
+        // the starting length is clamped so every body fits on the board
+        let snakes = if settings.versus {
+            let length = settings.start_length.clamp(1, width / 4 + 1);
+            vec![
+                Snake::new(
+                    Point {
+                        x: width / 4,
+                        y: height / 3,
+                    },
+                    DirectionEnum::Right,
+                    length,
+                ),
+                Snake::new(
+                    Point {
+                        x: width - 1 - width / 4,
+                        y: height - 1 - height / 3,
+                    },
+                    DirectionEnum::Left,
+                    length,
+                ),
+            ]
+        } else {
+            let length = settings.start_length.clamp(1, width / 2 + 1);
+            let center = Point {
+                x: width / 2,
+                y: height / 2,
+            };
+            let (head, dir) = settings
+                .random_start
+                .then(|| Self::random_start(&mut rng, width, height, length, settings.shape))
+                .flatten()
+                .unwrap_or((center, DirectionEnum::Right));
+            vec![Snake::new(head, dir, length)]
+        };
+        let start_length = snakes[0].body.len();
+
+        let occupied = snakes.iter().flat_map(|s| s.body.iter().copied()).collect();
+        let mut g = Self {
+            settings: Settings {
+                apple_count: settings.apple_count.max(1),
+                ..settings.clone()
+            },
+            snakes,
+            occupied,
+            apple: Point { x: 0, y: 0 },
+            apples: Vec::new(),
+            special: None,
+            apples_eaten: 0,
+            poison: None,
+            next_apple: None,
+            next_apple_rng: None,
+            magnet: None,
+            magnet_until: None,
+            portals: None,
+            walls: HashSet::new(),
+            maze_region: None,
+            big_apple: None,
+            lives: settings.lives,
+            combo: 0,
+            last_eat: None,
+            combos_enabled: false,
+            combo_log: Vec::new(),
+            replayed_combos: None,
+            target: None,
+            speedrun_goal: None,
+            target_score: None,
+            won: false,
+            splits: Vec::new(),
+            best_splits: Vec::new(),
+            finish_time: None,
+            cleared: false,
+            inset: 0,
+            theme,
+            random_theme: settings.random_theme,
+            bullet_ticks: 0,
+            trail: None,
+            ghost: None,
+            daily: None,
+            high_score: 0,
+            rng,
+            seed,
+            ticks: 0,
+            turns: Vec::new(),
+            resizes: Vec::new(),
+            start_size: (width, height),
+            playback: false,
+            score: 0,
+            width,
+            height,
+            game_over: false,
+            paused: false,
+            show_help: false,
+            winner: None,
+            countdown: Some(COUNTDOWN_FROM),
+            time_limit: None,
+            hardcore: false,
+            start: Instant::now(),
+            time_up: false,
+            boost_until: None,
+            level_banner_until: None,
+            grace_warning_until: None,
+            vanished: 0,
+            level: 1,
+            base_tick_ms: settings.base_tick_ms,
+            invincible: false,
+            practice: false,
+            carried: 0,
+            // Zen mode has no walls to hit
+            wrap: if settings.zen {
+                WrapMode::Both
+            } else {
+                settings.wrap
+            },
+            start_length,
+            autopilot: false,
+            events: Vec::new(),
+            history: None,
+        };
+        if settings.maze {
+            g.carve_maze();
+        } else if settings.wall_preset != WallPreset::None {
+            g.place_obstacles(settings.wall_preset);
+        }
+        if settings.portals {
+            g.place_portals();
+        }
+        g.place_apple();
+        g
+    }
+
+    /// A random head and heading for a snake of `length`, with the body
+    /// trailing behind the head and [`RESPAWN_CLEARANCE`] cells ahead of it,
+    /// all inside the board shape
+    fn random_start(
+        rng: &mut StdRng,
+        width: u16,
+        height: u16,
+        length: u16,
+        shape: BoardShape,
+    ) -> Option<(Point, DirectionEnum)> {
+        for _ in 0..100 {
+            let dir = DirectionEnum::ALL[rng.gen_range(0..DirectionEnum::ALL.len())];
+            let head = Point {
+                x: rng.gen_range(0..width),
+                y: rng.gen_range(0..height),
+            };
+            if Self::fits_at(head, dir, length, width, height, |p| {
+                shape.contains(p, width, height)
+            }) {
+                return Some((head, dir));
+            }
+        }
+        None
+    }
+
+    /// True if a snake of `length` with its head at `head`, facing `dir`,
+    /// fits on a `width` x `height` board with [`RESPAWN_CLEARANCE`] cells
+    /// ahead of it, and `open` accepts every one of those cells
+    fn fits_at(
+        head: Point,
+        dir: DirectionEnum,
+        length: u16,
+        width: u16,
+        height: u16,
+        open: impl Fn(Point) -> bool,
+    ) -> bool {
+        let (dx, dy): (i32, i32) = match dir {
+            DirectionEnum::Up => (0, -1),
+            DirectionEnum::Down => (0, 1),
+            DirectionEnum::Left => (-1, 0),
+            DirectionEnum::Right => (1, 0),
+        };
+        // Body cells count back from the head, clearance cells forward
+        (-(length as i32 - 1)..=RESPAWN_CLEARANCE as i32).all(|i| {
+            let x = head.x as i32 + i * dx;
+            let y = head.y as i32 + i * dy;
+            (0..width as i32).contains(&x)
+                && (0..height as i32).contains(&y)
+                && open(Point {
+                    x: x as u16,
+                    y: y as u16,
+                })
+        })
+    }
+
+    /// Starts a campaign level; the level's walls replace any board features
+    /// that would add walls, and cells around the snakes are kept clear
+    pub fn from_level(level: &Level, settings: &Settings, seed: u64) -> Self {
+        let settings = Settings {
+            versus: false,
+            maze: false,
+            wall_preset: WallPreset::None,
+            shape: BoardShape::Rectangle,
+            ..settings.clone()
+        };
+        let mut g = Self::from_board(level.width, level.height, &settings, seed);
+        if let Some(start) = level.start {
+            g.place_start(start, &level.walls);
+        }
+        let mut clear: HashSet<Point> = g.occupied.clone();
+        for s in &g.snakes {
+            clear.extend(g.neighbor(s.head(), s.dir));
+        }
+        g.walls = level
+            .walls
+            .iter()
+            .copied()
+            .filter(|p| !clear.contains(p))
+            .collect();
+        g.target = level.target;
+        if g.portals.is_some() {
+            g.place_portals();
+        }
+        g.special = None;
+        g.poison = None;
+        g.magnet = None;
+        match level.apple {
+            Some(apple) if !g.occupied.contains(&apple) => {
+                g.big_apple = None;
+                g.apple = apple;
+            }
+            _ => g.place_apple(),
+        }
+        g
+    }
+
+    /// Moves player one's snake so its head is at `start`, facing whichever
+    /// way leaves its body and the cell ahead clear of `walls`; a snake that
+    /// fits no way round starts as just its head
+    fn place_start(&mut self, start: Point, walls: &[Point]) {
+        let open = |p: Point| !walls.contains(&p);
+        // The body trails back from the head, away from the way it faces
+        let body_facing = |d: DirectionEnum| {
+            let back = DirectionEnum::ALL
+                .into_iter()
+                .find(|b| b.is_reverse_of(d))?;
+            let mut body = vec![start];
+            while body.len() < self.start_length {
+                let p = self.neighbor(*body.last()?, back)?;
+                if !open(p) || body.contains(&p) {
+                    return None;
+                }
+                body.push(p);
+            }
+            self.neighbor(start, d).filter(|&p| open(p))?;
+            Some((d, body))
+        };
+        let (dir, body) = DirectionEnum::ALL
+            .into_iter()
+            .find_map(body_facing)
+            .unwrap_or((DirectionEnum::Right, vec![start]));
+        for p in std::mem::take(&mut self.snakes[0].body) {
+            self.occupied.remove(&p);
+        }
+        self.snakes[0] = Snake::new(start, dir, 1);
+        self.snakes[0].body = body;
+        self.start_length = self.snakes[0].body.len();
+        self.occupied.extend(self.snakes[0].body.iter().copied());
+    }
+
+    /// Board dimensions that fit a terminal of `width` x `height`
+    /// characters exactly, after the spacing's margin, header and status
+    /// line and the ruler, if on
+    pub fn board_size(width: u16, height: u16, settings: &Settings) -> (u16, u16) {
+        let cell_width = settings.cell_width * if settings.square_cells { 2 } else { 1 };
+        let (columns, rows) = settings.spacing.board_area(width, height);
+        let ruler = u16::from(settings.ruler);
+        let height = rows.saturating_sub(ruler).max(MIN_BOARD_HEIGHT);
+        let columns = columns.saturating_sub(ruler * ruler_width(height));
+        ((columns / cell_width).max(MIN_BOARD_WIDTH), height)
+    }
+
+    /// Board size for `--width` and `--height`: each requested side big
+    /// enough to play on, otherwise the side that fits a terminal of
+    /// `columns` x `rows`
+    ///
+    /// Sides larger than the terminal are kept; the view scrolls to follow
+    /// the snake.
+    pub fn sized_board(
+        width: Option<u16>,
+        height: Option<u16>,
+        columns: u16,
+        rows: u16,
+        settings: &Settings,
+    ) -> (u16, u16) {
+        let (fit_width, fit_height) = Self::board_size(columns, rows, settings);
+        (
+            width.filter(|&w| w >= MIN_BOARD_WIDTH).unwrap_or(fit_width),
+            height
+                .filter(|&h| h >= MIN_BOARD_HEIGHT)
+                .unwrap_or(fit_height),
+        )
+    }
+
+    /// Terminal columns each board cell takes up
+    pub fn cell_width(&self) -> u16 {
+        self.settings.cell_width * if self.settings.square_cells { 2 } else { 1 }
+    }
+
+    /// Switches to the next theme in [`THEMES`], wrapping around
+    pub fn cycle_theme(&mut self) {
+        self.theme = (self.theme + 1) % THEMES.len();
+        self.random_theme = false;
+    }
+
+    /// Palette this game is drawn with
+    pub fn theme(&self) -> &'static Theme {
+        &THEMES[self.theme]
+    }
+
+    /// Direction from `from` to the next cell `to`, across a wrapped edge if
+    /// need be; `None` if the two aren't neighbors (e.g. across a portal)
+    pub fn link(&self, from: Point, to: Point) -> Option<DirectionEnum> {
+        DirectionEnum::ALL
+            .into_iter()
+            .find(|&d| self.neighbor(from, d) == Some(to))
+    }
+
+    /// True when two snakes are competing
+    pub fn is_versus(&self) -> bool {
+        self.snakes.len() > 1
+    }
+
+    /// Puts snake `i` back at its starting length nearest the center with
+    /// [`RESPAWN_CLEARANCE`] free cells ahead, so it can't crash straight
+    /// away; false if nowhere on the board fits
+    ///
+    /// Facing right is preferred, then any heading that fits at the same
+    /// distance from the center.
+    pub fn respawn(&mut self, i: usize) -> bool {
+        for p in std::mem::take(&mut self.snakes[i].body) {
+            self.occupied.remove(&p);
+        }
+        let length = self.start_length as u16;
+        let center = Point {
+            x: self.width / 2,
+            y: self.height / 2,
+        };
+        const HEADINGS: [DirectionEnum; 4] = [
+            DirectionEnum::Right,
+            DirectionEnum::Left,
+            DirectionEnum::Up,
+            DirectionEnum::Down,
+        ];
+        let fits = |head: Point, dir: DirectionEnum| {
+            Self::fits_at(head, dir, length, self.width, self.height, |p| {
+                !self.is_blocked(p) && !self.is_apple(p)
+            })
+        };
+        let Some((_, head, dir)) = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .flat_map(|p| {
+                HEADINGS
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(k, d)| (k, p, d))
+            })
+            .filter(|&(_, p, d)| fits(p, d))
+            .min_by_key(|&(k, p, _)| (p.x.abs_diff(center.x) + p.y.abs_diff(center.y), k))
+        else {
+            return false;
+        };
+
+        let score = self.snakes[i].score;
+        self.snakes[i] = Snake::new(head, dir, length);
+        self.snakes[i].score = score;
+        self.occupied.extend(self.snakes[i].body.iter().copied());
+        true
+    }
+
+    /// Adapts the board to a resized terminal
+    ///
+    /// If a snake no longer fits the game ends; food outside the new bounds
+    /// is dropped or moved back onto the board.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.resizes.push((self.ticks, width, height));
+        self.width = width;
+        self.height = height;
+        let in_bounds = |p: &Point| p.x < width && p.y < height;
+
+        if !self.game_over && !self.occupied.iter().all(in_bounds) {
+            self.game_over = true;
+            self.events.push(GameEvent::GameOver);
+        }
+        if self.special.as_ref().is_some_and(|s| !in_bounds(&s.pos)) {
+            self.special = None;
+        }
+        if self.poison.is_some_and(|p| !in_bounds(&p)) {
+            self.poison = None;
+        }
+        if self.magnet.is_some_and(|p| !in_bounds(&p)) {
+            self.magnet = None;
+        }
+        if self.next_apple.is_some_and(|p| !in_bounds(&p)) {
+            self.next_apple = None;
+            self.next_apple_rng = None;
+        }
+        if self
+            .portals
+            .is_some_and(|(a, b)| !in_bounds(&a) || !in_bounds(&b))
+        {
+            self.portals = None;
+        }
+        self.walls.retain(in_bounds);
+        self.maze_region = None;
+        // A smaller board may not leave room for the current border inset
+        self.inset = self
+            .inset
+            .min(width.min(height).saturating_sub(MIN_ARENA) / 2);
+        if !self.apple_cells().iter().all(|&p| self.in_arena(p)) {
+            self.big_apple = None;
+            self.apple = self.random_free_cell().unwrap_or(Point { x: 1, y: 1 });
+        }
+        let apples = std::mem::take(&mut self.apples);
+        self.apples = apples.into_iter().filter(|&p| self.in_arena(p)).collect();
+        self.refill_apples();
+    }
+
+    /// Places a new apple randomly on the board
+    pub fn place_apple(&mut self) {
+        // Big apple mode falls back to a normal apple when no 2x2 block fits
+        self.big_apple = None;
+        if self.settings.big_apples
+            && let Some(block) = self.random_free_block()
+        {
+            self.big_apple = Some(block);
+            self.apple = block[0];
+        } else {
+            // A hinted cell is kept to, unless something has moved onto it;
+            // the RNG then carries on from after the draw that picked it
+            let hinted = self.next_apple.take().filter(|&p| self.is_free_cell(p));
+            let drawn = self.next_apple_rng.take();
+            let cell = match (hinted, self.settings.apple_placement) {
+                (Some(p), _) => {
+                    if let Some(rng) = drawn {
+                        self.rng = rng;
+                    }
+                    Some(p)
+                }
+                (None, ApplePlacement::Uniform) => self.random_apple_cell(),
+                (None, ApplePlacement::Spread) => self.spread_apple_cell(),
+            };
+            self.apple = cell.unwrap_or(Point { x: 1, y: 1 });
+        }
+
+        // Occasionally drop a golden apple as well
+        if self.special.is_none()
+            && self.rng.gen_bool(SPECIAL_APPLE_CHANCE)
+            && let Some(pos) = self.random_free_cell()
+        {
+            self.special = Some(SpecialApple {
+                pos,
+                expires_at: self.ticks + SPECIAL_APPLE_TTL,
+            });
+        }
+
+        // Rarely drop a poison item that shrinks the snake
+        if self.settings.poison && self.poison.is_none() && self.rng.gen_bool(POISON_CHANCE) {
+            self.poison = self.random_free_cell();
+        }
+
+        if self.settings.magnets && self.magnet.is_none() && self.rng.gen_bool(MAGNET_CHANCE) {
+            self.magnet = self.random_free_cell();
+        }
+
+        self.refill_apples();
+
+        // Peek at the next apple on the real RNG, then put it back; the state
+        // after the draw is kept for when the hint is used, so the apple
+        // skips no draw a game with hints off would make
+        self.next_apple = None;
+        self.next_apple_rng = None;
+        if self.settings.apple_hints && self.big_apple.is_none() {
+            let rng = self.rng.clone();
+            self.next_apple = match self.settings.apple_placement {
+                ApplePlacement::Uniform => self.random_apple_cell(),
+                ApplePlacement::Spread => self.spread_apple_cell(),
+            };
+            self.next_apple_rng = Some(std::mem::replace(&mut self.rng, rng));
+        }
+    }
+
+    /// Tops the extra apples back up to `apple_count`, each on a free cell
+    fn refill_apples(&mut self) {
+        while self.apples.len() + 1 < self.settings.apple_count {
+            match self.random_apple_cell() {
+                Some(p) => self.apples.push(p),
+                None => break,
+            }
+        }
+    }
+
+    /// Puts a linked pair of portals on two free cells
+    pub fn place_portals(&mut self) {
+        self.portals = None;
+        if let Some(a) = self.random_free_cell() {
+            // Mark the first portal so the second can't land on it
+            self.portals = Some((a, a));
+            self.portals = self.random_free_cell().map(|b| (a, b));
+        }
+    }
+
+    /// True if `p` is either end of the portal pair
+    pub fn is_portal(&self, p: Point) -> bool {
+        self.portals.is_some_and(|(a, b)| p == a || p == b)
+    }
+
+    /// Where a head entering `p` ends up: the other portal, or `p` itself
+    pub fn teleport(&self, p: Point) -> Point {
+        match self.portals {
+            Some((a, b)) if p == a => b,
+            Some((a, b)) if p == b => a,
+            _ => p,
+        }
+    }
+
+    /// Cell a snake moving in direction `d` from `p` lands on, after portals
+    pub fn next_cell(&self, p: Point, d: DirectionEnum) -> Option<Point> {
+        self.neighbor(p, d).map(|n| self.teleport(n))
+    }
+
+    /// True if a snake moving onto `p` would crash into a body or wall
+    pub fn is_blocked(&self, p: Point) -> bool {
+        self.occupied.contains(&p) || self.walls.contains(&p) || !self.in_arena(p)
+    }
+
+    /// True if `p` lies inside the board shape and the shrinking border
+    pub fn in_arena(&self, p: Point) -> bool {
+        p.x >= self.inset
+            && p.y >= self.inset
+            && p.x < self.width.saturating_sub(self.inset)
+            && p.y < self.height.saturating_sub(self.inset)
+            && self.settings.shape.contains(p, self.width, self.height)
+    }
+
+    /// Closes the outermost open ring, down to a minimum arena size
+    ///
+    /// Snakes caught in the ring are crushed; food in it moves or vanishes.
+    pub fn shrink_arena(&mut self) {
+        let inset = self.inset + 1;
+        if self.width.saturating_sub(2 * inset) < MIN_ARENA
+            || self.height.saturating_sub(2 * inset) < MIN_ARENA
+        {
+            return;
+        }
+        self.inset = inset;
+        self.maze_region = None;
+        for i in 0..self.snakes.len() {
+            if self.snakes[i].body.iter().any(|&p| !self.in_arena(p)) {
+                self.snakes[i].alive = false;
+            }
+        }
+        if self.special.as_ref().is_some_and(|s| !self.in_arena(s.pos)) {
+            self.special = None;
+        }
+        if self.poison.is_some_and(|p| !self.in_arena(p)) {
+            self.poison = None;
+        }
+        if self.magnet.is_some_and(|p| !self.in_arena(p)) {
+            self.magnet = None;
+        }
+        if self
+            .portals
+            .is_some_and(|(a, b)| !self.in_arena(a) || !self.in_arena(b))
+        {
+            self.portals = None;
+        }
+        if !self.apple_cells().iter().all(|&p| self.in_arena(p)) {
+            self.big_apple = None;
+            self.apple = self.random_free_cell().unwrap_or(Point {
+                x: self.inset,
+                y: self.inset,
+            });
+        }
+    }
+
+    /// Adds one wall tile on a free cell, unless the cap is reached
+    ///
+    /// Cells right in front of a head are skipped, and a tile that would cut
+    /// a snake off from part of its free space is taken back.
+    pub fn spawn_wall(&mut self) -> bool {
+        let cap = MAX_WALLS.min(self.width as usize * self.height as usize / 10);
+        if self.walls.len() >= cap {
+            return false;
+        }
+        let room = |g: &Self| -> Vec<usize> {
+            g.snakes
+                .iter()
+                .map(|s| {
+                    if s.alive {
+                        g.reachable_cells(s.head())
+                    } else {
+                        0
+                    }
+                })
+                .collect()
+        };
+        let before = room(self);
+        for _ in 0..20 {
+            let Some(cand) = self.random_free_cell() else {
+                return false;
+            };
+            let in_front = self
+                .snakes
+                .iter()
+                .any(|s| s.alive && self.next_cell(s.head(), s.dir) == Some(cand));
+            if in_front {
+                continue;
+            }
+            // The wall itself may only cost each snake the one cell it covers
+            self.walls.insert(cand);
+            let sealed_off = room(self)
+                .iter()
+                .zip(&before)
+                .any(|(&after, &before)| after + 1 < before);
+            if !sealed_off {
+                self.maze_region = None;
+                return true;
+            }
+            self.walls.remove(&cand);
+        }
+        false
+    }
+
+    /// Picks a random cell not covered by a snake or any food
+    pub fn random_free_cell(&mut self) -> Option<Point> {
+        for _ in 0..1000 {
+            // Only inside the shrinking border (the whole board when it's off)
+            let x = self.rng.gen_range(self.inset..self.width - self.inset);
+            let y = self.rng.gen_range(self.inset..self.height - self.inset);
+            let cand = Point { x, y };
+            if self.is_free_cell(cand) {
+                return Some(cand);
+            }
+        }
+        None
+    }
+
+    /// True if nothing (snake, food, portal or wall) is on `p` and it's
+    /// inside the arena
+    pub fn is_free_cell(&self, p: Point) -> bool {
+        self.in_arena(p)
+            && !self.occupied.contains(&p)
+            && !self.is_apple(p)
+            && !self.special.as_ref().is_some_and(|s| s.pos == p)
+            && self.poison != Some(p)
+            && self.magnet != Some(p)
+            && !self.is_portal(p)
+            && !self.walls.contains(&p)
+    }
+
+    /// Picks a 2x2 block of free cells for a big apple, if one can be found
+    pub fn random_free_block(&mut self) -> Option<[Point; 4]> {
+        if self.width.saturating_sub(2 * self.inset) < 2
+            || self.height.saturating_sub(2 * self.inset) < 2
+        {
+            return None;
+        }
+        for _ in 0..1000 {
+            let x = self.rng.gen_range(self.inset..self.width - self.inset - 1);
+            let y = self.rng.gen_range(self.inset..self.height - self.inset - 1);
+            let block = [
+                Point { x, y },
+                Point { x: x + 1, y },
+                Point { x, y: y + 1 },
+                Point { x: x + 1, y: y + 1 },
+            ];
+            let free = block.iter().all(|&p| {
+                self.in_arena(p)
+                    && !self.occupied.contains(&p)
+                    && !self.special.as_ref().is_some_and(|s| s.pos == p)
+                    && self.poison != Some(p)
+                    && self.magnet != Some(p)
+                    && !self.is_portal(p)
+                    && !self.walls.contains(&p)
+            });
+            if free {
+                return Some(block);
+            }
+        }
+        None
+    }
+
+    /// Cells covered by the apple: one, or four for a big apple
+    pub fn apple_cells(&self) -> Vec<Point> {
+        match self.big_apple {
+            Some(block) => block.to_vec(),
+            None => vec![self.apple],
+        }
+    }
+
+    /// True if `p` is any cell of the apple
+    pub fn is_apple(&self, p: Point) -> bool {
+        let main = match self.big_apple {
+            Some(block) => block.contains(&p),
+            None => p == self.apple,
+        };
+        main || self.apples.contains(&p)
+    }
+
+    /// Picks a free cell for the apple; in a maze, only one the player can
+    /// reach, so the apple never lands in a sealed-off pocket
+    pub fn random_apple_cell(&mut self) -> Option<Point> {
+        if !self.settings.maze {
+            return self.random_free_cell();
+        }
+        let head = self.snakes[0].head();
+        let region = match self.maze_region.take() {
+            Some(region) if region.contains(&head) => region,
+            _ => self.open_region(head),
+        };
+        let cell = (0..100)
+            .map_while(|_| self.random_free_cell())
+            .find(|cand| region.contains(cand));
+        self.maze_region = Some(region);
+        cell
+    }
+
+    /// Picks the sampled cell farthest from the previous apple and the head
+    pub fn spread_apple_cell(&mut self) -> Option<Point> {
+        let (apple, head) = (self.apple, self.snakes[0].head());
+        let distance = |a: Point, b: Point| a.x.abs_diff(b.x) + a.y.abs_diff(b.y);
+        (0..SPREAD_SAMPLES)
+            .filter_map(|_| self.random_apple_cell())
+            .max_by_key(|&p| distance(p, apple) + distance(p, head))
+    }
+
+    /// Generates a random maze with a recursive backtracker
+    ///
+    /// Cells with even coordinates are rooms and the rest start as wall;
+    /// carving knocks through the walls between rooms until every room is
+    /// connected. The snakes' bodies and the cells in front of them are then
+    /// cleared, which keeps them joined to the maze.
+    pub fn carve_maze(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let head = self.snakes[0].head();
+        let start = Point {
+            x: head.x & !1,
+            y: head.y & !1,
+        };
+        let mut open = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(&room) = stack.last() {
+            let next: Vec<(Point, Point)> = [(0, -1), (0, 1), (-1, 0), (1, 0)]
+                .into_iter()
+                .filter_map(|(dx, dy): (i16, i16)| {
+                    let x = room.x.checked_add_signed(2 * dx)?;
+                    let y = room.y.checked_add_signed(2 * dy)?;
+                    let between = Point {
+                        x: room.x.checked_add_signed(dx)?,
+                        y: room.y.checked_add_signed(dy)?,
+                    };
+                    Some((between, Point { x, y }))
+                })
+                .filter(|&(_, p)| p.x < width && p.y < height && !open.contains(&p))
+                .collect();
+            if next.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let (between, room) = next[self.rng.gen_range(0..next.len())];
+            open.insert(between);
+            open.insert(room);
+            stack.push(room);
+        }
+        for s in &self.snakes {
+            open.extend(s.body.iter().copied());
+            open.extend(self.neighbor(s.head(), s.dir));
+        }
+        self.walls = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point { x, y }))
+            .filter(|p| !open.contains(p))
+            .collect();
+        self.maze_region = None;
+    }
+
+    /// Adds the preset's walls, moving any snake they'd cover or block
+    /// within [`RESPAWN_CLEARANCE`] cells to the nearest open start
+    fn place_obstacles(&mut self, preset: WallPreset) {
+        self.walls.extend(preset.cells(self.width, self.height));
+        self.maze_region = None;
+        for i in 0..self.snakes.len() {
+            let snake = &self.snakes[i];
+            let mut ahead = Some(snake.head());
+            let boxed_in = snake.body.iter().any(|p| self.walls.contains(p))
+                || (0..RESPAWN_CLEARANCE).any(|_| {
+                    ahead = ahead.and_then(|p| self.neighbor(p, snake.dir));
+                    ahead.is_some_and(|p| self.walls.contains(&p))
+                });
+            if !boxed_in {
+                continue;
+            }
+            let body = snake.body.clone();
+            let dir = snake.dir;
+            if !self.respawn(i) {
+                // Nowhere fits: keep the snake and open the walls around it
+                let ahead = self.neighbor(body[0], dir);
+                self.walls
+                    .retain(|p| !body.contains(p) && Some(*p) != ahead);
+                self.occupied.extend(body.iter().copied());
+                self.snakes[i].body = body;
+            }
+        }
+    }
+
+    /// Cells reachable from `start` without crossing a wall, ignoring snakes
+    pub fn open_region(&self, start: Point) -> HashSet<Point> {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(p) = stack.pop() {
+            for d in DirectionEnum::ALL {
+                if let Some(n) = self.next_cell(p, d)
+                    && !self.walls.contains(&n)
+                    && self.in_arena(n)
+                    && seen.insert(n)
+                {
+                    stack.push(n);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Queues a turn for the given player's snake
+    pub fn set_direction(&mut self, player: usize, d: DirectionEnum) {
+        if let Some(snake) = self.snakes.get_mut(player) {
+            snake.set_direction(d);
+        }
+    }
+
+    /// Queues a quarter turn counter-clockwise from where `player` will be
+    /// heading, for the relative control scheme
+    pub fn turn_left(&mut self, player: usize) {
+        self.rotate(player, false);
+    }
+
+    /// Queues a quarter turn clockwise from where `player` will be heading
+    pub fn turn_right(&mut self, player: usize) {
+        self.rotate(player, true);
+    }
+
+    /// Turns relative to the last queued heading, so two quick presses make
+    /// a U-turn over two ticks
+    fn rotate(&mut self, player: usize, clockwise: bool) {
+        if let Some(snake) = self.snakes.get_mut(player) {
+            let heading = snake.dir_queue.back().copied().unwrap_or(snake.dir);
+            snake.set_direction(heading.rotated(clockwise));
+        }
+    }
+
+    /// Snake and segment index covering `p`, if any
+    pub fn segment_at(&self, p: Point) -> Option<(usize, usize)> {
+        if !self.occupied.contains(&p) {
+            return None;
+        }
+        self.snakes
+            .iter()
+            .enumerate()
+            .find_map(|(i, s)| s.body.iter().position(|&b| b == p).map(|j| (i, j)))
+    }
+
+    /// Advances the start countdown by one second, clearing it at zero
+    pub fn tick_countdown(&mut self) {
+        self.countdown = match self.countdown {
+            Some(n) if n > 1 => Some(n - 1),
+            _ => None,
+        };
+        // The clock starts with the first move
+        if self.countdown.is_none() {
+            self.start = Instant::now();
+        }
+    }
+
+    /// Turns player one toward a board cell along its dominant axis
+    pub fn steer_toward(&mut self, target: Point) {
+        let head = self.snakes[0].head();
+        let dx = target.x as i32 - head.x as i32;
+        let dy = target.y as i32 - head.y as i32;
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        let dir = if dx.abs() >= dy.abs() {
+            if dx > 0 {
+                DirectionEnum::Right
+            } else {
+                DirectionEnum::Left
+            }
+        } else if dy > 0 {
+            DirectionEnum::Down
+        } else {
+            DirectionEnum::Up
+        };
+        self.set_direction(0, dir);
+    }
+
+    /// Time remaining in a time-attack run
+    pub fn time_left(&self) -> Option<Duration> {
+        let limit = self.time_limit?;
+        if self.countdown.is_some() {
+            return Some(limit);
+        }
+        Some(limit.saturating_sub(self.start.elapsed()))
+    }
+
+    /// Ends a time-attack run once its limit has elapsed
+    pub fn check_time(&mut self) {
+        if !self.game_over && self.time_left() == Some(Duration::ZERO) {
+            self.time_up = true;
+            self.game_over = true;
+            self.events.push(GameEvent::GameOver);
+        }
+    }
+
+    /// Game tick — moves snakes, checks collisions, updates score
+    pub fn step(&mut self) {
+        if self.game_over || self.cleared || self.paused || self.countdown.is_some() {
+            return;
+        }
+
+        if self.history.is_some() {
+            let snapshot = self.snapshot();
+            if let Some(history) = self.history.as_mut() {
+                if history.len() == HISTORY_DEPTH {
+                    history.pop_front();
+                }
+                history.push_back(snapshot);
+            }
+        }
+
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.step();
+        }
+
+        // The slowdown budget only refills once the way ahead is clear
+        if self.bullet_time_active() {
+            self.bullet_ticks += 1;
+        } else if !self.crash_ahead() {
+            self.bullet_ticks = 0;
+        }
+
+        // Ghost trail fades one step per tick
+        if let Some(trail) = self.trail.as_mut() {
+            trail.retain_mut(|(_, life)| {
+                *life -= 1;
+                *life > 0
+            });
+        }
+
+        // Golden apple vanishes once its timer runs out
+        if self
+            .special
+            .as_ref()
+            .is_some_and(|s| s.expires_at <= self.ticks)
+        {
+            self.special = None;
+        }
+
+        // Settle every snake's direction for this tick
+        for i in 0..self.snakes.len() {
+            let prev_dir = self.snakes[i].dir;
+            if i == 0 && self.autopilot {
+                self.snakes[0].dir_queue.clear();
+                if let Some(d) = self.autopilot_direction(0) {
+                    self.snakes[0].dir = d;
+                }
+            }
+            self.snakes[i].apply_queued_turn();
+            if self.snakes[i].dir != prev_dir {
+                self.turns.push((self.ticks, i, self.snakes[i].dir));
+            }
+        }
+        self.ticks += 1;
+
+        // The border closes in on a fixed tick schedule
+        if self.settings.shrinking && self.ticks.is_multiple_of(SHRINK_EVERY) {
+            self.shrink_arena();
+        }
+
+        // An active magnet drags the apple a cell closer before anyone moves,
+        // so a snake can meet it this very tick
+        if self.magnet_until.is_some_and(|until| self.ticks <= until) {
+            self.pull_apple();
+        } else {
+            self.magnet_until = None;
+        }
+
+        // Check collisions with borders, any body, or another head; a head
+        // stepping into a portal comes out of the other one and collides there
+        let mut new_heads: Vec<Option<Point>> = self
+            .snakes
+            .iter()
+            .map(|s| {
+                if s.alive {
+                    self.next_cell(s.head(), s.dir)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for i in 0..new_heads.len() {
+            if !self.snakes[i].alive {
+                continue;
+            }
+            let new_head = new_heads[i];
+            // Zen mode never crashes; snakes pass through bodies harmlessly.
+            // Without self-collision a snake's own body is only scenery
+            let crashed = match new_head {
+                None => true,
+                Some(_) if self.settings.zen => false,
+                Some(p) => {
+                    let own_body = !self.settings.self_collision
+                        && self.segment_at(p).is_some_and(|(j, _)| j == i)
+                        && !self
+                            .snakes
+                            .iter()
+                            .enumerate()
+                            .any(|(j, s)| j != i && s.body.contains(&p));
+                    let blocked = if own_body {
+                        self.walls.contains(&p) || !self.in_arena(p)
+                    } else {
+                        self.is_blocked(p)
+                    };
+                    blocked
+                        || new_heads
+                            .iter()
+                            .enumerate()
+                            .any(|(j, other)| j != i && *other == Some(p))
+                }
+            };
+            let hit_wall = match new_head {
+                None => true,
+                Some(p) => self.walls.contains(&p) || !self.in_arena(p),
+            };
+            // With wall grace the first wall hit only holds the snake in place
+            // for a tick, giving the player a chance to turn away
+            if crashed && hit_wall && self.settings.wall_grace && !self.snakes[i].grace_used {
+                self.snakes[i].grace_used = true;
+                new_heads[i] = None;
+                self.events.push(GameEvent::WallGrace { player: i });
+            } else if crashed && self.invincible {
+                // Practice mode holds at walls and slides through bodies
+                if hit_wall {
+                    new_heads[i] = None;
+                }
+            } else if crashed && !self.is_versus() && self.lives > 1 {
+                // A spare life puts the snake back on the board, score intact
+                self.lives -= 1;
+                new_heads[i] = None;
+                if self.respawn(i) {
+                    self.events.push(GameEvent::LifeLost { player: i });
+                } else {
+                    self.snakes[i].alive = false;
+                }
+            } else if crashed {
+                self.snakes[i].alive = false;
+            }
+        }
+
+        let score_before = self.score;
+        for (i, new_head) in new_heads.into_iter().enumerate() {
+            if let Some(new_head) = new_head
+                && self.snakes[i].alive
+            {
+                self.advance(i, new_head);
+            }
+        }
+
+        // A runner apple takes its own step once the snakes have moved
+        if self.settings.apple_moves {
+            self.run_apple();
+        }
+
+        // Reaching the target clears a campaign level
+        if self.target.is_some_and(|t| self.score >= t) {
+            self.cleared = true;
+            self.events.push(GameEvent::LevelCleared);
+        }
+
+        // Reaching the goal wins, even on a tick where a snake also crashed
+        if self.target_score.is_some_and(|t| self.score >= t) {
+            self.won = true;
+            self.game_over = true;
+        }
+
+        // The board closes in a little every few points
+        if self.settings.closing_walls && self.score / WALL_EVERY > score_before / WALL_EVERY {
+            self.spawn_wall();
+        }
+
+        // Solo games end on the first crash; versus ends with one snake left
+        let alive: Vec<usize> = (0..self.snakes.len())
+            .filter(|&i| self.snakes[i].alive)
+            .collect();
+        if self.is_versus() {
+            if alive.len() <= 1 {
+                self.game_over = true;
+                self.winner = alive.first().copied();
+            }
+        } else if alive.is_empty() {
+            self.game_over = true;
+        }
+        if self.game_over {
+            self.events.push(GameEvent::GameOver);
+        }
+        if (self.game_over || self.cleared) && self.finish_time.is_none() {
+            self.finish_time = Some(self.run_time());
+        }
+    }
+
+    /// Moves the apple one cell toward the nearest living head, along the
+    /// longer axis first; it stays put rather than land on a snake, wall
+    /// or another item
+    fn pull_apple(&mut self) {
+        // A 2x2 apple is too heavy to pull
+        if self.big_apple.is_some() {
+            return;
+        }
+        let apple = self.apple;
+        let distance = |h: &Point| h.x.abs_diff(apple.x) + h.y.abs_diff(apple.y);
+        let Some(head) = self
+            .snakes
+            .iter()
+            .filter(|s| s.alive)
+            .map(Snake::head)
+            .min_by_key(distance)
+        else {
+            return;
+        };
+        let toward = |from: u16, to: u16| match from.cmp(&to) {
+            std::cmp::Ordering::Less => from + 1,
+            std::cmp::Ordering::Greater => from - 1,
+            std::cmp::Ordering::Equal => from,
+        };
+        let across = Point {
+            x: toward(apple.x, head.x),
+            y: apple.y,
+        };
+        let down = Point {
+            x: apple.x,
+            y: toward(apple.y, head.y),
+        };
+        let order = if apple.x.abs_diff(head.x) >= apple.y.abs_diff(head.y) {
+            [across, down]
+        } else {
+            [down, across]
+        };
+        if let Some(cell) = order
+            .into_iter()
+            .find(|&p| p != apple && self.apple_can_move_to(p))
+        {
+            self.apple = cell;
+        }
+    }
+
+    /// True if the apple may move onto `p`: an open arena cell with no snake,
+    /// wall or other item on it
+    fn apple_can_move_to(&self, p: Point) -> bool {
+        !self.is_blocked(p)
+            && !self.special.as_ref().is_some_and(|s| s.pos == p)
+            && self.poison != Some(p)
+            && self.magnet != Some(p)
+            && !self.is_portal(p)
+            && !self.apples.contains(&p)
+    }
+
+    /// Steps a runner apple to a random neighbouring cell, preferring ones
+    /// that don't bring it closer to the nearest head
+    ///
+    /// Cells with fewer than two open neighbours are skipped, so the apple
+    /// never backs itself into a dead end a snake can't reach and leave.
+    fn run_apple(&mut self) {
+        if self.big_apple.is_some() {
+            return;
+        }
+        let apple = self.apple;
+        let nearest = |p: Point| {
+            self.snakes
+                .iter()
+                .filter(|s| s.alive)
+                .map(|s| s.head().x.abs_diff(p.x) + s.head().y.abs_diff(p.y))
+                .min()
+                .unwrap_or(0)
+        };
+        let exits = |p: Point| {
+            DirectionEnum::ALL
+                .iter()
+                .filter_map(|&d| self.neighbor(p, d))
+                .filter(|&n| n == apple || self.apple_can_move_to(n))
+                .count()
+        };
+        let moves: Vec<Point> = DirectionEnum::ALL
+            .iter()
+            .filter_map(|&d| self.neighbor(apple, d))
+            .filter(|&p| self.apple_can_move_to(p) && exits(p) >= 2)
+            .collect();
+        let away: Vec<Point> = moves
+            .iter()
+            .copied()
+            .filter(|&p| nearest(p) >= nearest(apple))
+            .collect();
+        let pool = if away.is_empty() { moves } else { away };
+        if !pool.is_empty() {
+            self.apple = pool[self.rng.gen_range(0..pool.len())];
+        }
+    }
+
+    /// Starts remembering the last [`HISTORY_DEPTH`] steps so they can be
+    /// undone
+    pub fn keep_history(&mut self) {
+        self.history.get_or_insert_with(VecDeque::new);
+    }
+
+    /// Copy of everything a step can change
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            snakes: self.snakes.clone(),
+            apple: self.apple,
+            apples: self.apples.clone(),
+            next_apple: self.next_apple,
+            next_apple_rng: self.next_apple_rng.clone(),
+            big_apple: self.big_apple,
+            special: self.special.clone(),
+            poison: self.poison,
+            magnet: self.magnet,
+            magnet_until: self.magnet_until,
+            portals: self.portals,
+            walls: self.walls.clone(),
+            combo: self.combo,
+            last_eat: self.last_eat,
+            bullet_ticks: self.bullet_ticks,
+            splits: self.splits.clone(),
+            trail: self.trail.clone(),
+            ghost: self.ghost.as_ref().map(|g| g.progress()),
+            inset: self.inset,
+            score: self.score,
+            apples_eaten: self.apples_eaten,
+            level: self.level,
+            lives: self.lives,
+            ticks: self.ticks,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Rewinds the most recent step, putting back any apple it ate; false
+    /// when there's no history left
+    ///
+    /// The run counts as practice from then on, and turns recorded after
+    /// the restored tick are dropped so a replay stays consistent.
+    pub fn undo(&mut self) -> bool {
+        let Some(s) = self.history.as_mut().and_then(VecDeque::pop_back) else {
+            return false;
+        };
+        self.occupied = s
+            .snakes
+            .iter()
+            .flat_map(|s| s.body.iter().copied())
+            .collect();
+        self.snakes = s.snakes;
+        self.apple = s.apple;
+        self.apples = s.apples;
+        self.next_apple = s.next_apple;
+        self.next_apple_rng = s.next_apple_rng;
+        self.big_apple = s.big_apple;
+        self.special = s.special;
+        self.poison = s.poison;
+        self.magnet = s.magnet;
+        self.magnet_until = s.magnet_until;
+        self.portals = s.portals;
+        self.walls = s.walls;
+        self.maze_region = None;
+        self.combo = s.combo;
+        self.last_eat = s.last_eat;
+        self.bullet_ticks = s.bullet_ticks;
+        self.splits = s.splits;
+        self.trail = s.trail;
+        if let (Some(ghost), Some((ticks, ended))) = (self.ghost.as_mut(), s.ghost) {
+            ghost.rewind(ticks, ended);
+        }
+        self.inset = s.inset;
+        self.score = s.score;
+        self.apples_eaten = s.apples_eaten;
+        self.level = s.level;
+        self.lives = s.lives;
+        self.ticks = s.ticks;
+        self.rng = s.rng;
+        self.turns.retain(|&(tick, _, _)| tick < self.ticks);
+        self.combo_log.retain(|&(tick, _)| tick < self.ticks);
+        self.game_over = false;
+        self.cleared = false;
+        self.won = false;
+        self.winner = None;
+        self.finish_time = None;
+        self.practice = true;
+        true
+    }
+
+    /// Makes reaching `goal` points finish the game, timing splits on the way
+    pub fn start_speedrun(&mut self, goal: u32, best_splits: Vec<Duration>) {
+        self.speedrun_goal = Some(goal);
+        self.target = Some(goal);
+        self.best_splits = best_splits;
+    }
+
+    /// Time since the first move, not counting pauses, frozen once the
+    /// game is over
+    pub fn run_time(&self) -> Duration {
+        match self.finish_time {
+            Some(time) => time,
+            None if self.countdown.is_some() => Duration::ZERO,
+            None => self.start.elapsed(),
+        }
+    }
+
+    /// Latest split and how far ahead (negative) or behind it is of the
+    /// personal best at the same mark, in milliseconds
+    pub fn split_delta(&self) -> Option<(usize, i64)> {
+        let i = self.splits.len().checked_sub(1)?;
+        let best = self.best_splits.get(i)?;
+        Some((
+            i,
+            self.splits[i].as_millis() as i64 - best.as_millis() as i64,
+        ))
+    }
+
+    /// Takes any splits the score has just passed
+    fn take_splits(&mut self) {
+        let Some(goal) = self.speedrun_goal else {
+            return;
+        };
+        let marks = split_marks(goal);
+        while let Some(&mark) = marks.get(self.splits.len())
+            && self.score >= mark
+        {
+            self.splits.push(self.run_time());
+        }
+    }
+
+    /// Takes the events collected since the last call
+    ///
+    /// Callers should drain after stepping so the list doesn't grow unbounded.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Factor applied to apple points; the current level when the level
+    /// multiplier is on, otherwise 1
+    pub fn multiplier(&self) -> u32 {
+        if self.settings.level_multiplier {
+            self.level
+        } else {
+            1
+        }
+    }
+
+    /// Combo multiplier while the window since the last apple is still open
+    pub fn active_combo(&self) -> Option<u32> {
+        let last = self.last_eat?;
+        (self.combo > 1 && last.elapsed() < COMBO_WINDOW).then_some(self.combo)
+    }
+
+    /// Whether the combo went up recently enough to flash in the header
+    pub fn combo_flashing(&self) -> bool {
+        self.active_combo().is_some()
+            && self
+                .last_eat
+                .is_some_and(|last| last.elapsed() < COMBO_FLASH)
+    }
+
+    /// Extends or restarts the combo for an apple eaten now; a replay
+    /// takes the recorded combo for this tick instead
+    fn bump_combo(&mut self) -> u32 {
+        let now = Instant::now();
+        self.combo = match (&self.replayed_combos, self.last_eat) {
+            (Some(recorded), _) => recorded
+                .iter()
+                .find(|&&(tick, _)| tick == self.ticks)
+                .map_or(1, |&(_, combo)| combo),
+            (None, _) if !self.combos_enabled => 1,
+            (None, Some(last)) if now.duration_since(last) < COMBO_WINDOW => {
+                (self.combo + 1).min(MAX_COMBO)
+            }
+            (None, _) => 1,
+        };
+        if self.combo > 1 {
+            self.combo_log.push((self.ticks, self.combo));
+        }
+        self.last_eat = Some(now);
+        self.combo
+    }
+
+    /// Moves snake `i` onto `new_head`, eating whatever is there
+    pub fn advance(&mut self, i: usize, new_head: Point) {
+        // Move snake forward
+        self.snakes[i].body.insert(0, new_head);
+        self.occupied.insert(new_head);
+        self.events.push(GameEvent::Moved { player: i });
+
+        // Check apple collision
+        let mut ate = false;
+        if self.is_apple(new_head) {
+            let extra = self.apples.iter().position(|&p| p == new_head);
+            let size = if self.big_apple.is_some() && extra.is_none() {
+                BIG_APPLE_POINTS
+            } else {
+                1
+            };
+            let combo = self.bump_combo();
+            let points = size * self.settings.apple_value * self.multiplier() * combo;
+            self.score += points;
+            self.apples_eaten += 1;
+            self.snakes[i].score += points;
+            self.snakes[i].pending_growth += self.settings.growth_per_apple - 1;
+            ate = true;
+            match extra {
+                // An extra apple is replaced on its own; the others stay put
+                Some(k) => {
+                    self.apples.remove(k);
+                    self.refill_apples();
+                }
+                None => self.place_apple(),
+            }
+        }
+
+        // Golden apple is worth bonus points and an extra segment
+        if self.special.as_ref().is_some_and(|s| s.pos == new_head) {
+            self.special = None;
+            self.score += SPECIAL_APPLE_POINTS;
+            self.apples_eaten += 1;
+            self.snakes[i].score += SPECIAL_APPLE_POINTS;
+            self.snakes[i].pending_growth += self.settings.growth_per_apple;
+            ate = true;
+        }
+
+        if ate {
+            self.take_splits();
+            self.snakes[i].grace_used = false;
+            self.events.push(GameEvent::AppleEaten { player: i });
+            let level = 1 + (self.earned() / POINTS_PER_LEVEL);
+            if level > self.level {
+                self.events.push(GameEvent::LevelUp(level));
+            }
+            self.level = level;
+        } else if self.snakes[i].pending_growth > 0 {
+            self.snakes[i].pending_growth -= 1;
+        } else {
+            self.pop_tail(i);
+        }
+
+        // At the length cap growth is dropped and the tail keeps moving
+        if let Some(cap) = self.settings.max_length {
+            while self.snakes[i].body.len() > cap {
+                self.pop_tail(i);
+            }
+            if self.snakes[i].body.len() == cap {
+                self.snakes[i].pending_growth = 0;
+            }
+        }
+
+        // A magnet starts pulling the apple toward the snakes
+        if self.magnet == Some(new_head) {
+            self.magnet = None;
+            self.magnet_until = Some(self.ticks + MAGNET_TICKS);
+        }
+
+        // Poison trims the tail, but never below the starting length
+        if self.poison == Some(new_head) {
+            self.poison = None;
+            let removable = self.snakes[i].body.len().saturating_sub(self.start_length);
+            for _ in 0..removable.min(POISON_SHRINK) {
+                self.pop_tail(i);
+            }
+        }
+    }
+
+    /// Removes snake `i`'s last segment, keeping the occupied set in sync
+    pub fn pop_tail(&mut self, i: usize) {
+        if let Some(tail) = self.snakes[i].body.pop() {
+            // Cosmetic only: trail cells never enter the occupied set
+            if let Some(trail) = self.trail.as_mut() {
+                trail.push_back((tail, TRAIL_LIFE));
+            }
+            // Zen mode, body passthrough and practice let segments overlap,
+            // and an overlap can outlast the mode, so another segment may
+            // still cover the cell
+            if self.segment_at(tail).is_none() {
+                self.occupied.remove(&tail);
+            }
+        }
+    }
+
+    /// Cell one step from `p` in direction `d`, or `None` past a solid wall
+    ///
+    /// Edges on a wrapping axis lead to the opposite edge instead.
+    pub fn neighbor(&self, p: Point, d: DirectionEnum) -> Option<Point> {
+        let wrap_x = self.wrap.wraps_x();
+        let wrap_y = self.wrap.wraps_y();
+        let (x, y) = match d {
+            DirectionEnum::Up => (
+                Some(p.x),
+                p.y.checked_sub(1)
+                    .or_else(|| wrap_y.then(|| self.height - 1)),
+            ),
+            DirectionEnum::Down => (
+                Some(p.x),
+                Some(p.y + 1)
+                    .filter(|&y| y < self.height)
+                    .or_else(|| wrap_y.then_some(0)),
+            ),
+            DirectionEnum::Left => (
+                p.x.checked_sub(1)
+                    .or_else(|| wrap_x.then(|| self.width - 1)),
+                Some(p.y),
+            ),
+            DirectionEnum::Right => (
+                Some(p.x + 1)
+                    .filter(|&x| x < self.width)
+                    .or_else(|| wrap_x.then_some(0)),
+                Some(p.y),
+            ),
+        };
+        Some(Point { x: x?, y: y? })
+    }
+
+    /// Picks the autopilot's next move: the first step of a shortest path
+    /// to the apple, or else the safe move with the most room around it
+    pub fn autopilot_direction(&self, player: usize) -> Option<DirectionEnum> {
+        let snake = &self.snakes[player];
+        let head = snake.head();
+        let is_free = |p: Point| !self.is_blocked(p);
+
+        // Breadth-first search from the head, remembering each path's first move
+        let mut seen = HashSet::from([head]);
+        let mut queue = VecDeque::new();
+        for d in DirectionEnum::ALL {
+            if d.is_reverse_of(snake.dir) {
+                continue;
+            }
+            if let Some(n) = self.next_cell(head, d)
+                && is_free(n)
+                && seen.insert(n)
+            {
+                if self.is_apple(n) {
+                    return Some(d);
+                }
+                queue.push_back((n, d));
+            }
+        }
+        while let Some((p, first)) = queue.pop_front() {
+            for d in DirectionEnum::ALL {
+                if let Some(n) = self.next_cell(p, d)
+                    && is_free(n)
+                    && seen.insert(n)
+                {
+                    if self.is_apple(n) {
+                        return Some(first);
+                    }
+                    queue.push_back((n, first));
+                }
+            }
+        }
+
+        // No path to the apple: stay alive in the roomiest direction
+        DirectionEnum::ALL
+            .into_iter()
+            .filter(|d| !d.is_reverse_of(snake.dir))
+            .filter_map(|d| {
+                let n = self.next_cell(head, d).filter(|&n| is_free(n))?;
+                Some((self.reachable_cells(n), d))
+            })
+            .max_by_key(|(room, _)| *room)
+            .map(|(_, d)| d)
+    }
+
+    /// Counts free cells reachable from `start` (flood fill)
+    pub fn reachable_cells(&self, start: Point) -> usize {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(p) = stack.pop() {
+            for d in DirectionEnum::ALL {
+                if let Some(n) = self.next_cell(p, d)
+                    && !self.is_blocked(n)
+                    && seen.insert(n)
+                {
+                    stack.push(n);
+                }
+            }
+        }
+        seen.len()
+    }
+
+    /// True if `p` is visible through the fog (Chebyshev distance from a head)
+    pub fn is_lit(&self, p: Point) -> bool {
+        // The whole board is revealed once the game ends
+        let Some(radius) = self.settings.fog_radius.filter(|_| !self.game_over) else {
+            return true;
+        };
+        self.snakes.iter().filter(|s| s.alive).any(|s| {
+            let head = s.head();
+            head.x.abs_diff(p.x).max(head.y.abs_diff(p.y)) <= radius
+        })
+    }
+
+    /// Fraction of the board covered by snakes, from 0.0 to 1.0
+    pub fn fill_ratio(&self) -> f64 {
+        // usize math: width * height can overflow u16 on big boards
+        let cells = self.width as usize * self.height as usize;
+        if cells == 0 {
+            return 0.0;
+        }
+        self.occupied.len() as f64 / cells as f64
+    }
+
+    /// Time left on the golden apple, estimated at the current speed
+    pub fn special_remaining(&self) -> Option<Duration> {
+        let special = self.special.as_ref()?;
+        let ticks = special.expires_at.saturating_sub(self.ticks) as u32;
+        Some(self.tick_duration() * ticks)
+    }
+
+    /// Points scored in this game, leaving out any carried-over bonus;
+    /// levels, speed and the leaderboard all go by this
+    pub fn earned(&self) -> u32 {
+        self.score - self.carried
+    }
+
+    /// Points the next game starts with after this one is lost; only solo
+    /// games without a target carry anything over
+    pub fn carry_bonus(&self) -> u32 {
+        if !self.game_over || self.is_versus() || self.target.is_some() {
+            return 0;
+        }
+        self.score * self.settings.carry_over / 100
+    }
+
+    /// Starts this game with `bonus` points already on the board
+    pub fn start_with_bonus(&mut self, bonus: u32) {
+        self.score += bonus;
+        self.snakes[0].score += bonus;
+        self.carried += bonus;
+    }
+
+    /// Time left on an active magnet, estimated at the current speed
+    pub fn magnet_remaining(&self) -> Option<Duration> {
+        let until = self.magnet_until?;
+        let ticks = until.saturating_sub(self.ticks) as u32;
+        Some(self.tick_duration() * ticks)
+    }
+
+    /// Board, snakes, apple and score as a JSON object, for `--dump`
+    pub fn to_json(&self) -> String {
+        let point = |p: Point| format!("{{\"x\":{},\"y\":{}}}", p.x, p.y);
+        let snakes: Vec<String> = self
+            .snakes
+            .iter()
+            .map(|s| {
+                let body: Vec<String> = s.body.iter().map(|&p| point(p)).collect();
+                format!(
+                    "{{\"direction\":\"{}\",\"alive\":{},\"score\":{},\"body\":[{}]}}",
+                    s.dir.name(),
+                    s.alive,
+                    s.score,
+                    body.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"width\":{},\"height\":{},\"score\":{},\"level\":{},\"game_over\":{},\"apple\":{},\"snakes\":[{}]}}",
+            self.width,
+            self.height,
+            self.score,
+            self.level,
+            self.game_over,
+            point(self.apple),
+            snakes.join(",")
+        )
+    }
+
+    /// Recording of this run so far, for saving as a replay
+    pub fn replay(&self) -> Replay {
+        Replay {
+            seed: self.seed,
+            width: self.start_size.0,
+            height: self.start_size.1,
+            settings: self.settings.clone(),
+            turns: self.turns.clone(),
+            resizes: self.resizes.clone(),
+            combos: self.combo_log.clone(),
+            score: self.score,
+            ticks: self.ticks,
+        }
+    }
+
+    /// A game set up to play `replay` back, scoring its recorded combos
+    pub fn for_replay(replay: &Replay) -> Self {
+        let mut game = Game::from_board(replay.width, replay.height, &replay.settings, replay.seed);
+        game.countdown = None;
+        game.playback = true;
+        game.replayed_combos = Some(replay.combos.clone());
+        game
+    }
+
+    /// True while a speed boost is running
+    pub fn boosting(&self) -> bool {
+        self.boost_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Turns practice mode on or off; either way the run stops counting
+    pub fn toggle_invincible(&mut self) {
+        self.invincible = !self.invincible;
+        self.practice = true;
+    }
+
+    /// Nudges the base speed by one step, within the allowed range
+    pub fn adjust_base_tick(&mut self, faster: bool) {
+        self.base_tick_ms = if faster {
+            self.base_tick_ms
+                .saturating_sub(TICK_ADJUST_MS)
+                .max(MIN_TICK_MS)
+        } else {
+            (self.base_tick_ms + TICK_ADJUST_MS).min(MAX_TICK_MS)
+        };
+    }
+
+    /// True when a live snake's next `bullet_distance` cells, along the way
+    /// it's about to move, run into a wall, a snake or the board edge
+    pub fn crash_ahead(&self) -> bool {
+        self.snakes.iter().filter(|s| s.alive).any(|snake| {
+            let dir = snake.dir_queue.front().copied().unwrap_or(snake.dir);
+            let mut p = snake.head();
+            (0..self.settings.bullet_distance).any(|_| match self.neighbor(p, dir) {
+                Some(next) if !self.is_blocked(next) => {
+                    p = next;
+                    false
+                }
+                _ => true,
+            })
+        })
+    }
+
+    /// True when bullet time is slowing this tick: it's on, a crash is
+    /// close and the run of slowed ticks hasn't used up its budget
+    pub fn bullet_time_active(&self) -> bool {
+        self.settings.bullet_time.is_some()
+            && self.bullet_ticks < BULLET_TIME_TICKS
+            && self.crash_ahead()
+    }
+
+    /// Controls snake speed (faster with higher levels, or with every
+    /// point scored when smooth acceleration is on, slower in bullet time)
+    pub fn tick_duration(&self) -> Duration {
+        let reduce = if self.settings.zen {
+            // Zen mode keeps a gentle constant pace
+            0
+        } else if self.settings.smooth_speed {
+            self.earned() as u64 * self.settings.difficulty.speed_step() / POINTS_PER_LEVEL as u64
+        } else {
+            (self.level - 1) as u64 * self.settings.difficulty.speed_step()
+        };
+        let mut ms = self.base_tick_ms.saturating_sub(reduce).max(MIN_TICK_MS);
+        if let Some(percent) = self.settings.bullet_time
+            && self.bullet_time_active()
+        {
+            ms = ms * percent as u64 / 100;
+        }
+        // Boost halves whatever the current speed is, even at the floor
+        if self.boosting() {
+            return Duration::from_millis(ms / 2);
+        }
+        Duration::from_millis(ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replaces the snake body, rebuilding the occupied set to match
+    fn set_snake(game: &mut Game, snake: Vec<Point>) {
+        game.occupied = snake.iter().copied().collect();
+        game.snakes[0].body = snake;
+    }
+
+    /// Builds a 20x10 wrap-walls game with the snake placed at the given cells
+    fn wrap_game(snake: Vec<Point>, dir: DirectionEnum) -> Game {
+        let settings = Settings {
+            wrap: WrapMode::Both,
+            ..Settings::default()
+        };
+        let mut game = Game::new(24, 19, &settings);
+        set_snake(&mut game, snake);
+        game.countdown = None;
+        game.snakes[0].dir = dir;
+        game.apple = Point { x: 10, y: 5 };
+        game
+    }
+
+    #[test]
+    fn wraps_off_right_edge_to_left() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 19, y: 2 },
+                Point { x: 18, y: 2 },
+                Point { x: 17, y: 2 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.step();
+        assert!(!game.game_over);
+        assert!(game.snakes[0].head() == Point { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn wraps_off_left_edge_to_right() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 0, y: 2 },
+                Point { x: 1, y: 2 },
+                Point { x: 2, y: 2 },
+            ],
+            DirectionEnum::Left,
+        );
+        game.step();
+        assert!(!game.game_over);
+        assert!(game.snakes[0].head() == Point { x: 19, y: 2 });
+    }
+
+    #[test]
+    fn wraps_off_top_edge_to_bottom() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 3, y: 0 },
+                Point { x: 3, y: 1 },
+                Point { x: 3, y: 2 },
+            ],
+            DirectionEnum::Up,
+        );
+        game.step();
+        assert!(!game.game_over);
+        assert!(game.snakes[0].head() == Point { x: 3, y: 9 });
+    }
+
+    #[test]
+    fn wraps_off_bottom_edge_to_top() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 3, y: 9 },
+                Point { x: 3, y: 8 },
+                Point { x: 3, y: 7 },
+            ],
+            DirectionEnum::Down,
+        );
+        game.step();
+        assert!(!game.game_over);
+        assert!(game.snakes[0].head() == Point { x: 3, y: 0 });
+    }
+
+    #[test]
+    fn wrap_modes_wrap_only_their_own_axis() {
+        // Snake pointing off each edge, and where it lands if that edge wraps
+        let edges = [
+            (
+                DirectionEnum::Right,
+                Point { x: 19, y: 2 },
+                Point { x: 0, y: 2 },
+            ),
+            (
+                DirectionEnum::Left,
+                Point { x: 0, y: 2 },
+                Point { x: 19, y: 2 },
+            ),
+            (
+                DirectionEnum::Up,
+                Point { x: 3, y: 0 },
+                Point { x: 3, y: 9 },
+            ),
+            (
+                DirectionEnum::Down,
+                Point { x: 3, y: 9 },
+                Point { x: 3, y: 0 },
+            ),
+        ];
+        for mode in WrapMode::ALL {
+            for (dir, head, wrapped) in edges {
+                // Body trails straight back from the head, away from the edge
+                let back = match dir {
+                    DirectionEnum::Right => DirectionEnum::Left,
+                    DirectionEnum::Left => DirectionEnum::Right,
+                    DirectionEnum::Up => DirectionEnum::Down,
+                    DirectionEnum::Down => DirectionEnum::Up,
+                };
+                let mut game = wrap_game(Vec::new(), dir);
+                game.wrap = mode;
+                let mut snake = vec![head];
+                for _ in 0..2 {
+                    let last = *snake.last().unwrap();
+                    snake.push(game.neighbor(last, back).unwrap());
+                }
+                set_snake(&mut game, snake);
+                game.step();
+
+                let wraps = match dir {
+                    DirectionEnum::Left | DirectionEnum::Right => mode.wraps_x(),
+                    DirectionEnum::Up | DirectionEnum::Down => mode.wraps_y(),
+                };
+                assert_eq!(game.game_over, !wraps, "{:?} moving {:?}", mode, dir);
+                if wraps {
+                    assert!(
+                        game.snakes[0].head() == wrapped,
+                        "{:?} moving {:?}",
+                        mode,
+                        dir
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eating_an_apple_emits_events() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 9, y: 5 },
+                Point { x: 8, y: 5 },
+                Point { x: 7, y: 5 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.step();
+        assert_eq!(
+            game.drain_events(),
+            vec![
+                GameEvent::Moved { player: 0 },
+                GameEvent::AppleEaten { player: 0 },
+            ]
+        );
+        assert!(game.drain_events().is_empty());
+
+        game.score = POINTS_PER_LEVEL - 1;
+        game.apple = Point { x: 11, y: 5 };
+        game.step();
+        assert_eq!(
+            game.drain_events(),
+            vec![
+                GameEvent::Moved { player: 0 },
+                GameEvent::AppleEaten { player: 0 },
+                GameEvent::LevelUp(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn apples_score_more_at_higher_levels() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 9, y: 5 },
+                Point { x: 8, y: 5 },
+                Point { x: 7, y: 5 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.settings.apple_value = 2;
+        game.settings.level_multiplier = true;
+        game.level = 3;
+        game.score = 3 * POINTS_PER_LEVEL - 7;
+        game.step();
+        assert_eq!(game.score, 3 * POINTS_PER_LEVEL - 1);
+        assert_eq!(game.snakes[0].score, 6);
+    }
+
+    #[test]
+    fn collision_emits_game_over() {
+        let mut game = Game::new(24, 19, &Settings::default());
+        set_snake(
+            &mut game,
+            vec![
+                Point { x: 19, y: 2 },
+                Point { x: 18, y: 2 },
+                Point { x: 17, y: 2 },
+            ],
+        );
+        game.countdown = None;
+        game.snakes[0].dir = DirectionEnum::Right;
+        game.step();
+        assert!(game.game_over);
+        assert_eq!(game.drain_events(), vec![GameEvent::GameOver]);
+    }
+
+    #[test]
+    fn border_color_follows_the_score_along_the_gradient() {
+        let theme = &THEMES[0];
+        let (r, g, b) = theme.border_gradient[0];
+        assert_eq!(theme.border_color(0, true), Color::Rgb(r, g, b));
+        let (r, g, b) = theme.border_gradient[1];
+        assert_eq!(
+            theme.border_color(BORDER_GRADIENT_SCORE / 2, true),
+            Color::Rgb(r, g, b)
+        );
+        let (r, g, b) = theme.border_gradient[2];
+        assert_eq!(theme.border_color(1000, true), Color::Rgb(r, g, b));
+        assert!(theme.border_color(10, true) != theme.border_color(20, true));
+
+        assert_eq!(theme.border_color(1000, false), theme.border);
+        let fixed = THEMES
+            .iter()
+            .find(|t| t.border_gradient.is_empty())
+            .unwrap();
+        assert_eq!(fixed.border_color(30, true), fixed.border);
+    }
+
+    #[test]
+    fn bullet_time_slows_near_a_crash_for_a_limited_run_of_ticks() {
+        let settings = Settings {
+            start_length: 1,
+            bullet_time: Some(200),
+            bullet_distance: 3,
+            ..Settings::default()
+        };
+        let mut game = Game::from_board(20, 10, &settings, 1);
+        game.countdown = None;
+        let normal = Duration::from_millis(game.base_tick_ms);
+        set_snake(&mut game, vec![Point { x: 2, y: 2 }]);
+        game.snakes[0].dir = DirectionEnum::Right;
+        game.apple = Point { x: 0, y: 9 };
+        assert_eq!(game.tick_duration(), normal);
+
+        // Circling a 3x3 room always has a wall within three cells ahead
+        game.walls = (0..20)
+            .flat_map(|x| (0..10).map(move |y| Point { x, y }))
+            .filter(|p| !(2..5).contains(&p.x) || !(2..5).contains(&p.y))
+            .collect();
+        for tick in 0..12 {
+            let expected = if tick < BULLET_TIME_TICKS {
+                normal * 2
+            } else {
+                normal
+            };
+            assert_eq!(game.tick_duration(), expected, "tick {}", tick);
+            let snake = &game.snakes[0];
+            let ahead = game.neighbor(snake.head(), snake.dir).unwrap();
+            if game.is_blocked(ahead) {
+                let turn = snake.dir.rotated(true);
+                game.snakes[0].dir_queue.push_back(turn);
+            }
+            game.step();
+            assert!(!game.game_over);
+        }
+
+        game.settings.bullet_time = None;
+        game.bullet_ticks = 0;
+        assert_eq!(game.tick_duration(), normal);
+    }
+
+    #[test]
+    fn respawn_keeps_clear_of_walls_around_the_center() {
+        let settings = Settings {
+            lives: 3,
+            ..Settings::default()
+        };
+        let mut game = Game::new(24, 19, &settings);
+        game.apple = Point { x: 0, y: 0 };
+        let center = Point { x: 10, y: 5 };
+        let layouts: [Vec<Point>; 4] = [
+            // A wall just right of the center
+            (3..8).map(|y| Point { x: 12, y }).collect(),
+            // The center itself walled in on every side
+            (8..13)
+                .flat_map(|x| (3..8).map(move |y| Point { x, y }))
+                .filter(|&p| p != center)
+                .collect(),
+            // Walls filling every row but the bottom one
+            (0..20)
+                .flat_map(|x| (0..9).map(move |y| Point { x, y }))
+                .collect(),
+            // Walls everywhere but one column, so the snake must face up or
+            // down
+            (0..20)
+                .filter(|&x| x != 3)
+                .flat_map(|x| (0..10).map(move |y| Point { x, y }))
+                .collect(),
+        ];
+        for walls in layouts {
+            game.walls = walls.into_iter().collect();
+            assert!(game.respawn(0));
+            let snake = &game.snakes[0];
+            let mut p = snake.head();
+            for _ in 0..RESPAWN_CLEARANCE {
+                p = game.neighbor(p, snake.dir).unwrap();
+                assert!(!game.is_blocked(p));
+            }
+            assert!(snake.body.iter().all(|p| !game.walls.contains(p)));
+        }
+
+        game.walls = (0..20)
+            .flat_map(|x| (0..10).map(move |y| Point { x, y }))
+            .collect();
+        assert!(!game.respawn(0));
+    }
+
+    #[test]
+    fn crash_with_lives_left_respawns_and_keeps_score() {
+        let settings = Settings {
+            lives: 2,
+            ..Settings::default()
+        };
+        let mut game = Game::new(24, 19, &settings);
+        set_snake(
+            &mut game,
+            vec![
+                Point { x: 19, y: 2 },
+                Point { x: 18, y: 2 },
+                Point { x: 17, y: 2 },
+            ],
+        );
+        game.countdown = None;
+        game.score = 4;
+        game.snakes[0].dir = DirectionEnum::Right;
+        game.apple = Point { x: 0, y: 0 };
+        game.step();
+        assert!(!game.game_over);
+        assert_eq!(game.lives, 1);
+        assert_eq!(game.score, 4);
+        assert!(game.snakes[0].head() == Point { x: 10, y: 5 });
+        assert_eq!(game.occupied.len(), 3);
+        assert_eq!(game.drain_events(), vec![GameEvent::LifeLost { player: 0 }]);
+
+        // The last life ends the game as usual
+        game.snakes[0].dir = DirectionEnum::Up;
+        for _ in 0..10 {
+            game.step();
+        }
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn shaped_boards_keep_food_inside_and_crash_at_the_edge() {
+        for shape in [BoardShape::Circle, BoardShape::Diamond] {
+            let settings = Settings {
+                shape,
+                ..Settings::default()
+            };
+            let mut game = Game::with_seed(44, 29, &settings, 7);
+            assert!(!shape.contains(Point { x: 0, y: 0 }, game.width, game.height));
+            for _ in 0..200 {
+                game.place_apple();
+                assert!(game.apple_cells().iter().all(|&p| game.in_arena(p)));
+            }
+
+            // Heading up a quarter of the way across runs out of the shape
+            // well before the top row
+            let x = game.width / 4;
+            set_snake(&mut game, (0..3).map(|i| Point { x, y: 12 + i }).collect());
+            game.countdown = None;
+            game.snakes[0].dir = DirectionEnum::Up;
+            game.apple = Point { x: 0, y: 0 };
+            for _ in 0..game.height {
+                game.step();
+            }
+            assert!(game.game_over, "{:?}", shape);
+            assert!(game.snakes[0].head().y > 0, "{:?}", shape);
+        }
+    }
+
+    #[test]
+    fn random_theme_is_reproduced_from_the_seed() {
+        let mut settings = Settings::default();
+        settings.set_kv("theme", RANDOM_THEME).unwrap();
+        assert!(settings.random_theme);
+        let mut restored = Settings::default();
+        for (key, value) in settings.to_kv() {
+            restored.set_kv(key, &value).unwrap();
+        }
+        let themes: HashSet<usize> = (0..20)
+            .map(|seed| {
+                let game = Game::from_board(20, 10, &settings, seed);
+                let replayed = Game::from_board(20, 10, &restored, seed);
+                assert_eq!(game.theme, replayed.theme);
+                game.theme
+            })
+            .collect();
+        assert!(themes.len() > 1);
+    }
+
+    #[test]
+    fn boards_load_from_text_with_start_and_apple() {
+        let text = "##########\n#   A    #\n#        #\n#S  ##\n##########\n";
+        let level = Level::parse_board("test", text).unwrap();
+        assert_eq!((level.width, level.height), (10, 5));
+        let game = Game::from_level(&level, &Settings::default(), 1);
+        assert!(game.snakes[0].head() == Point { x: 1, y: 3 });
+        assert!(game.apple == Point { x: 4, y: 1 });
+        assert!(game.walls.contains(&Point { x: 4, y: 3 }));
+        assert_eq!(game.target, None);
+        // The body never starts on a wall, shrinking to fit if need be
+        assert!(game.snakes[0].body.iter().all(|p| !game.walls.contains(p)));
+
+        assert!(
+            Level::parse_board(
+                "x",
+                "##########\n#        #\n#        #\n#        #\n##########"
+            )
+            .is_err()
+        );
+        assert!(
+            Level::parse_board(
+                "x",
+                "##########\n#S  S    #\n#        #\n#        #\n##########"
+            )
+            .is_err()
+        );
+        assert!(
+            Level::parse_board(
+                "x",
+                "##########\n#S  AA   #\n#        #\n#        #\n##########"
+            )
+            .is_err()
+        );
+        assert!(
+            Level::parse_board(
+                "x",
+                "##########\n#S  ?    #\n#        #\n#        #\n##########"
+            )
+            .is_err()
+        );
+        assert!(Level::parse_board("x", "#S#\n").is_err());
+    }
+
+    #[test]
+    fn speedrun_takes_splits_and_keeps_the_best() {
+        assert_eq!(split_marks(25), vec![10, 20, 25]);
+        assert_eq!(split_marks(20), vec![10, 20]);
+
+        let mut game = wrap_game(
+            vec![
+                Point { x: 9, y: 5 },
+                Point { x: 8, y: 5 },
+                Point { x: 7, y: 5 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.start_speedrun(20, Vec::new());
+        game.score = 9;
+        game.step();
+        assert_eq!(game.splits.len(), 1);
+        assert!(!game.cleared);
+        game.score = 19;
+        game.apple = Point { x: 11, y: 5 };
+        game.step();
+        assert_eq!(game.splits.len(), 2);
+        assert!(game.cleared);
+        let finish = game.run_time();
+        assert_eq!(game.finish_time, Some(finish));
+
+        let mut best = BestSplits::default();
+        let slow = [Duration::from_millis(900), Duration::from_millis(2000)];
+        let fast = [Duration::from_millis(1000), Duration::from_millis(1500)];
+        assert!(best.record(20, &slow));
+        assert!(best.record(20, &fast));
+        assert!(!best.record(20, &slow));
+        assert!(!best.record(20, &fast[..1]));
+        let restored = BestSplits::parse(&best.to_text()).unwrap();
+        assert_eq!(restored.get(20), Some(&fast[..]));
+        assert_eq!(restored.get(30), None);
+    }
+
+    #[test]
+    fn snake_never_grows_past_the_length_cap() {
+        let settings = Settings {
+            max_length: Some(6),
+            growth_per_apple: 2,
+            ..Settings::default()
+        };
+        let mut game = Game::with_seed(34, 24, &settings, 3);
+        game.countdown = None;
+        game.autopilot = true;
+        for _ in 0..2000 {
+            game.step();
+            assert!(game.snakes[0].body.len() <= 6);
+            assert_eq!(game.occupied.len(), game.snakes[0].body.len());
+            if game.game_over {
+                break;
+            }
+        }
+        assert!(game.apples_eaten > 5);
+        assert_eq!(game.snakes[0].body.len(), 6);
+    }
+
+    #[test]
+    fn undo_rewinds_a_step_and_uneats_the_apple() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 9, y: 5 },
+                Point { x: 8, y: 5 },
+                Point { x: 7, y: 5 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.keep_history();
+        let body = game.snakes[0].body.clone();
+        game.step();
+        assert_eq!(game.score, 1);
+        assert!(game.undo());
+        assert_eq!(game.score, 0);
+        assert_eq!(game.apples_eaten, 0);
+        assert!(game.apple == Point { x: 10, y: 5 });
+        assert!(game.snakes[0].body == body);
+        assert_eq!(game.occupied.len(), 3);
+        assert!(game.practice);
+        assert!(!game.undo());
+
+        for _ in 0..HISTORY_DEPTH + 10 {
+            game.step();
+        }
+        assert_eq!(game.history.as_ref().unwrap().len(), HISTORY_DEPTH);
+    }
+
+    #[test]
+    fn magnet_pulls_the_apple_but_not_through_walls() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 4, y: 2 },
+                Point { x: 3, y: 2 },
+                Point { x: 2, y: 2 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.magnet = Some(Point { x: 5, y: 2 });
+        game.step();
+        assert_eq!(game.magnet_until, Some(1 + MAGNET_TICKS));
+
+        // The longer axis closes first
+        game.step();
+        assert!(game.apple == Point { x: 9, y: 5 });
+        game.step();
+        assert!(game.apple == Point { x: 8, y: 5 });
+
+        // Walled in on both sides toward the head, the apple stays put
+        game.walls = HashSet::from([Point { x: 8, y: 4 }, Point { x: 7, y: 5 }]);
+        game.step();
+        assert!(game.apple == Point { x: 8, y: 5 });
+    }
+
+    #[test]
+    fn runner_apple_keeps_clear_of_snakes_walls_and_dead_ends() {
+        let settings = Settings {
+            apple_moves: true,
+            ..Settings::default()
+        };
+        let mut game = Game::from_board(20, 12, &settings, 11);
+        game.countdown = None;
+        game.autopilot = true;
+        game.walls = (0..8).map(|y| Point { x: 12, y }).collect();
+        for _ in 0..300 {
+            let (before, eaten) = (game.apple, game.apples_eaten);
+            game.step();
+            if game.game_over {
+                break;
+            }
+            let apple = game.apple;
+            assert!(!game.is_blocked(apple));
+            // Either eaten and replaced, or at most one cell away
+            let moved = before.x.abs_diff(apple.x) + before.y.abs_diff(apple.y);
+            assert!(game.apples_eaten > eaten || moved <= 1);
+        }
+    }
+
+    #[test]
+    fn carried_score_adds_to_the_total_but_not_the_level() {
+        let settings = Settings {
+            carry_over: 50,
+            ..Settings::default()
+        };
+        let mut game = Game::from_board(20, 12, &settings, 1);
+        game.score = 21;
+        assert_eq!(game.carry_bonus(), 0);
+        game.game_over = true;
+        let bonus = game.carry_bonus();
+        assert_eq!(bonus, 10);
+
+        let mut next = Game::from_board(20, 12, &settings, 2);
+        next.start_with_bonus(bonus);
+        assert_eq!(next.score, bonus);
+        assert_eq!(next.earned(), 0);
+        assert_eq!(
+            next.tick_duration(),
+            Game::from_board(20, 12, &settings, 2).tick_duration()
+        );
+        next.advance(0, next.apple);
+        assert_eq!(next.level, 1);
+        assert_eq!(next.earned(), 1);
+    }
+
+    #[test]
+    fn relative_turns_rotate_every_heading() {
+        use DirectionEnum::*;
+        for (heading, left, right) in [
+            (Up, Left, Right),
+            (Right, Up, Down),
+            (Down, Right, Left),
+            (Left, Down, Up),
+        ] {
+            let mut game = Game::from_board(20, 12, &Settings::default(), 1);
+            game.snakes[0].dir = heading;
+            game.turn_left(0);
+            assert_eq!(game.snakes[0].dir_queue.back(), Some(&left));
+            game.snakes[0].dir_queue.clear();
+            game.turn_right(0);
+            assert_eq!(game.snakes[0].dir_queue.back(), Some(&right));
+        }
+
+        // Two presses queue a U-turn one quarter at a time
+        let mut game = Game::from_board(20, 12, &Settings::default(), 1);
+        game.snakes[0].dir = Right;
+        game.turn_right(0);
+        game.turn_right(0);
+        assert_eq!(game.snakes[0].dir_queue, [Down, Left]);
+    }
+
+    #[test]
+    fn unreadable_leaderboard_is_backed_up_before_saving() {
+        let dir = std::env::temp_dir().join(format!("snake-test-{}", std::process::id()));
+        let path = dir.join("leaderboard");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "snake-leaderboard 2\nAAA 10 extra\n").unwrap();
+
+        let mut board = Leaderboard::load(&path);
+        assert!(board.entries.is_empty());
+        board.insert("BOB".to_string(), 7);
+        board.save(&path).unwrap();
+        let first = dir.join("leaderboard.bak");
+        assert_eq!(
+            fs::read_to_string(&first).unwrap(),
+            "snake-leaderboard 2\nAAA 10 extra\n"
+        );
+        assert_eq!(Leaderboard::load(&path).entries, board.entries);
+
+        // A readable board is simply replaced
+        board.save(&path).unwrap();
+        assert!(backup_path(&path) == dir.join("leaderboard.bak.1"));
+
+        // A second bad file gets its own backup beside the first
+        fs::write(&path, "snake-leaderboard 3\n").unwrap();
+        board.save(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("leaderboard.bak.1")).unwrap(),
+            "snake-leaderboard 3\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&first).unwrap(),
+            "snake-leaderboard 2\nAAA 10 extra\n"
+        );
+        assert_eq!(
+            Leaderboard::parse("snake-leaderboard 2\n").err().as_deref(),
+            Some("unsupported leaderboard version 2")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn board_size_leaves_room_for_the_chosen_spacing() {
+        let (width, height) = (80, 24);
+        for (spacing, size) in [
+            (Spacing::Compact, (78, 19)),
+            (Spacing::Normal, (76, 15)),
+            (Spacing::Roomy, (74, 13)),
+        ] {
+            let settings = Settings {
+                spacing,
+                ..Settings::default()
+            };
+            assert_eq!(Game::board_size(width, height, &settings), size);
+        }
+        let settings = Settings {
+            ruler: true,
+            ..Settings::default()
+        };
+        assert_eq!(Game::board_size(width, height, &settings), (73, 14));
+    }
+
+    #[test]
+    fn apple_hint_shows_the_next_apple_without_touching_the_rng() {
+        let hints = Settings {
+            apple_hints: true,
+            ..Settings::default()
+        };
+        let plain = Game::from_board(20, 10, &Settings::default(), 7);
+        let mut game = Game::from_board(20, 10, &hints, 7);
+        assert!(plain.next_apple.is_none());
+        assert!(plain.apple == game.apple);
+        assert_eq!(
+            plain.rng.clone().gen_range(0..u64::MAX),
+            game.rng.clone().gen_range(0..u64::MAX)
+        );
+
+        let hint = game.next_apple.unwrap();
+        assert!(hint != game.apple);
+        game.place_apple();
+        assert!(game.apple == hint);
+
+        // A hint the snake has since covered gives way to a fresh cell
+        let hint = game.next_apple.unwrap();
+        game.occupied.insert(hint);
+        game.place_apple();
+        assert!(game.apple != hint);
+    }
+
+    #[test]
+    fn body_passthrough_crosses_the_snake_without_dying() {
+        // Heading left with the body looping round below the head
+        let body = vec![
+            Point { x: 3, y: 4 },
+            Point { x: 4, y: 4 },
+            Point { x: 4, y: 5 },
+            Point { x: 3, y: 5 },
+            Point { x: 2, y: 5 },
+            Point { x: 1, y: 5 },
+        ];
+        for self_collision in [true, false] {
+            let settings = Settings {
+                self_collision,
+                ..Settings::default()
+            };
+            let mut game = Game::from_board(20, 10, &settings, 1);
+            game.countdown = None;
+            set_snake(&mut game, body.clone());
+            game.snakes[0].dir = DirectionEnum::Left;
+            game.apple = Point { x: 15, y: 8 };
+            game.snakes[0].dir_queue.push_back(DirectionEnum::Down);
+            for _ in 0..3 {
+                game.step();
+                // Cells covered twice stay occupied until both segments leave
+                let cells: HashSet<Point> = game.snakes[0].body.iter().copied().collect();
+                assert!(cells == game.occupied);
+            }
+            assert_eq!(game.game_over, self_collision);
+        }
+        assert_eq!(
+            Settings::default().value(SettingsItem::BodyPassthrough),
+            "off"
+        );
+    }
+
+    #[test]
+    fn daily_challenge_dates_seeds_and_bests() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_723), (2024, 1, 1));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+        assert_eq!(civil_date(-1), (1969, 12, 31));
+
+        assert_eq!(daily_seed("2026-10-15"), daily_seed("2026-10-15"));
+        assert_ne!(daily_seed("2026-10-15"), daily_seed("2026-10-16"));
+
+        let mut best = DailyBests::default();
+        assert!(best.record("2026-10-15", 12));
+        assert!(!best.record("2026-10-15", 12));
+        assert!(best.record("2026-10-15", 20));
+        assert!(best.record("2026-10-16", 3));
+        let parsed = DailyBests::parse(&best.to_text()).unwrap();
+        assert_eq!(parsed.get("2026-10-15"), Some(20));
+        assert_eq!(parsed.get("2026-10-16"), Some(3));
+        assert_eq!(parsed.get("2026-10-17"), None);
+
+        // The rules are fixed, only the look carries over
+        let mine = Settings {
+            lives: 3,
+            poison: true,
+            theme: 2,
+            ..Settings::default()
+        };
+        let daily = mine.daily();
+        assert_eq!(daily.lives, 1);
+        assert!(!daily.poison);
+        assert_eq!(daily.theme, 2);
+    }
+
+    #[test]
+    fn requested_board_sides_fall_back_when_too_small() {
+        let settings = Settings::default();
+        let fitted = Game::board_size(80, 24, &settings);
+        let game = Game::with_size(Some(30), None, 80, 24, &settings, 1);
+        assert_eq!((game.width, game.height), (30, fitted.1));
+        assert_eq!(
+            Game::sized_board(Some(3), Some(200), 80, 24, &settings),
+            (fitted.0, 200)
+        );
+        assert_eq!(Game::sized_board(None, None, 80, 24, &settings), fitted);
+    }
+
+    #[test]
+    fn high_score_only_grows_and_survives_bad_files() {
+        let dir = std::env::temp_dir().join(format!("snake-high-{}", std::process::id()));
+        let path = dir.join("highscore");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(read_high_score(&path), 0);
+        assert!(write_high_score(&path, 12).unwrap());
+        assert!(!write_high_score(&path, 12).unwrap());
+        assert!(!write_high_score(&path, 5).unwrap());
+        assert_eq!(read_high_score(&path), 12);
+        assert!(write_high_score(&path, 13).unwrap());
+        assert_eq!(read_high_score(&path), 13);
+
+        fs::write(&path, "not a number").unwrap();
+        assert_eq!(read_high_score(&path), 0);
+
+        // A newer version isn't read, and is backed up before a save
+        fs::write(&path, "snake-highscore 2\n500\n").unwrap();
+        assert_eq!(read_high_score(&path), 0);
+        assert!(write_high_score(&path, 3).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("highscore.bak")).unwrap(),
+            "snake-highscore 2\n500\n"
+        );
+
+        // A bare score from before the header is read, then migrated
+        fs::write(&path, "40\n").unwrap();
+        assert_eq!(read_high_score(&path), 40);
+        assert!(write_high_score(&path, 41).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("highscore.bak.1")).unwrap(),
+            "40\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "snake-highscore 1\n41\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("highscore.bak")).unwrap(),
+            "snake-highscore 2\n500\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replays_score_the_recorded_combos() {
+        let settings = Settings::default();
+        let mut game = Game::from_board(20, 10, &settings, 3);
+        game.countdown = None;
+        game.autopilot = true;
+        game.combos_enabled = true;
+        for _ in 0..300 {
+            game.step();
+        }
+        // Stepping this fast chains every apple after the first
+        assert!(game.apples_eaten > 2);
+        assert!(!game.combo_log.is_empty());
+        let replay = Replay::parse(&game.replay().to_text()).unwrap();
+
+        let mut again = Game::for_replay(&replay);
+        for tick in 0..300 {
+            for &(_, player, d) in replay.turns.iter().filter(|t| t.0 == tick) {
+                again.snakes[player].dir_queue.push_back(d);
+            }
+            again.step();
+        }
+        assert_eq!(again.score, game.score);
+
+        // Without combos turned on every apple is worth its plain value
+        let mut plain = Game::from_board(20, 10, &settings, 3);
+        plain.countdown = None;
+        plain.autopilot = true;
+        for _ in 0..300 {
+            plain.step();
+        }
+        assert_eq!(plain.score, plain.apples_eaten);
+        assert!(plain.combo_log.is_empty());
+    }
+
+    #[test]
+    fn ghost_retraces_a_recorded_run_and_then_stops() {
+        let settings = Settings::default();
+        let mut game = Game::from_board(20, 10, &settings, 5);
+        game.countdown = None;
+        let mut path = Vec::new();
+        for tick in 0..6 {
+            if tick == 2 {
+                game.set_direction(0, DirectionEnum::Down);
+            }
+            game.step();
+            path.push(game.snakes[0].head());
+        }
+        assert!(!game.game_over);
+        let replay = Replay::parse(&game.replay().to_text()).unwrap();
+        assert_eq!((replay.score, replay.ticks), (game.score, 6));
+
+        let mut ghost = Ghost::new(replay);
+        for head in path {
+            ghost.step();
+            assert!(ghost.body().unwrap()[0] == head);
+        }
+        ghost.step();
+        assert!(ghost.finished());
+        assert!(ghost.body().is_none());
+    }
+
+    #[test]
+    fn random_starts_fit_the_board_and_repeat_with_the_seed() {
+        let settings = Settings {
+            random_start: true,
+            start_length: 4,
+            ..Settings::default()
+        };
+        let mut starts = HashSet::new();
+        for seed in 0..40 {
+            let game = Game::from_board(20, 10, &settings, seed);
+            let snake = &game.snakes[0];
+            assert_eq!(snake.body.len(), 4);
+            assert!(snake.body.iter().all(|p| p.x < 20 && p.y < 10));
+            assert_eq!(game.occupied.len(), 4);
+            // Each segment sits one step behind the one before it
+            for pair in snake.body.windows(2) {
+                assert!(game.neighbor(pair[1], snake.dir) == Some(pair[0]));
+            }
+            let mut ahead = snake.head();
+            for _ in 0..RESPAWN_CLEARANCE {
+                ahead = game.neighbor(ahead, snake.dir).unwrap();
+                assert!(!game.is_blocked(ahead));
+            }
+
+            let again = Game::from_board(20, 10, &settings, seed);
+            assert!(again.snakes[0].body == snake.body);
+            assert_eq!(again.snakes[0].dir, snake.dir);
+            starts.insert((snake.head(), snake.dir.name()));
+        }
+        assert!(starts.len() > 10);
+    }
+
+    #[test]
+    fn settings_file_keeps_only_the_saved_settings() {
+        let settings = Settings {
+            theme: 2,
+            poison: true,
+            ..Settings::default()
+        };
+        let text = settings.saved_text();
+        assert!(!text.contains("poison"));
+        let saved = Settings::parse_saved(&text).unwrap();
+        assert_eq!(saved.theme, 2);
+        assert!(!saved.poison);
+        assert!(Settings::parse_saved("theme Neon\n").is_err());
+
+        // Saving goes through the same versioned write as the other stores
+        let dir = std::env::temp_dir().join(format!("snake-settings-{}", std::process::id()));
+        let path = dir.join("settings");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "snake-settings 2\ntheme 9\n").unwrap();
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load_saved(&path).theme, 2);
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.bak")).unwrap(),
+            "snake-settings 2\ntheme 9\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn maze_is_connected_around_the_snake() {
+        let settings = Settings {
+            maze: true,
+            ..Settings::default()
+        };
+        for seed in 0..20 {
+            let game = Game::with_seed(44, 29, &settings, seed);
+            assert!(!game.walls.is_empty());
+            let snake = &game.snakes[0];
+            let region = game.open_region(snake.head());
+            let open = (game.width as usize * game.height as usize) - game.walls.len();
+            assert_eq!(region.len(), open);
+            assert!(snake.body.iter().all(|p| !game.walls.contains(p)));
+            let front = game.next_cell(snake.head(), snake.dir).unwrap();
+            assert!(!game.walls.contains(&front));
+            assert!(region.contains(&game.apple));
+        }
+    }
+
+    #[test]
+    fn campaign_levels_parse_and_start_clear() {
+        let levels = Level::campaign();
+        assert!(levels.len() > 1);
+        for level in &levels {
+            let game = Game::from_level(level, &Settings::default(), 1);
+            let head = game.snakes[0].head();
+            let front = game.next_cell(head, game.snakes[0].dir).unwrap();
+            assert!(!game.walls.contains(&front));
+            assert!(!game.walls.contains(&game.apple));
+            assert_eq!(game.target, level.target);
+        }
+        assert!(Level::parse_all("level 5 Bad\n....\n...\n").is_err());
+    }
+
+    #[test]
+    fn spread_apples_land_farther_from_the_last_one() {
+        let average_jump = |apple_placement| {
+            let settings = Settings {
+                apple_placement,
+                ..Settings::default()
+            };
+            let mut game = Game::with_seed(44, 29, &settings, 7);
+            let mut total = 0;
+            for _ in 0..500 {
+                let prev = game.apple;
+                game.place_apple();
+                total += prev.x.abs_diff(game.apple.x) + prev.y.abs_diff(game.apple.y);
+            }
+            total as f64 / 500.0
+        };
+        let uniform = average_jump(ApplePlacement::Uniform);
+        let spread = average_jump(ApplePlacement::Spread);
+        assert!(spread > uniform, "spread {} vs uniform {}", spread, uniform);
+    }
+
+    #[test]
+    fn occupied_set_stays_in_sync_with_long_snake() {
+        // 100x50 board with a 2000-segment snake folded over the top 20 rows
+        let mut game = Game::new(104, 59, &Settings::default());
+        let mut body = Vec::new();
+        for y in 0..20 {
+            let xs: Vec<u16> = if y % 2 == 0 {
+                (0..100).collect()
+            } else {
+                (0..100).rev().collect()
+            };
+            body.extend(xs.into_iter().map(|x| Point { x, y }));
+        }
+        body.reverse();
+        assert_eq!(body.len(), 2000);
+        set_snake(&mut game, body);
+        game.countdown = None;
+        game.snakes[0].dir = DirectionEnum::Left;
+        game.set_direction(0, DirectionEnum::Down);
+        game.place_apple();
+
+        for _ in 0..25 {
+            game.step();
+            assert!(!game.game_over);
+            let body = &game.snakes[0].body;
+            let from_vec: HashSet<Point> = body.iter().copied().collect();
+            assert_eq!(from_vec.len(), body.len());
+            assert!(from_vec == game.occupied);
+            assert!(!game.occupied.contains(&game.apple));
+        }
+    }
+
+    #[test]
+    fn autopilot_collects_apples_headless() {
+        let mut game = Game::new(24, 19, &Settings::default());
+        game.countdown = None;
+        game.autopilot = true;
+        for _ in 0..500 {
+            game.step();
+            if game.game_over {
+                break;
+            }
+        }
+        assert!(game.score >= 5, "autopilot only scored {}", game.score);
+    }
+
+    #[test]
+    fn same_seed_places_apples_identically() {
+        let mut a = Game::with_seed(24, 19, &Settings::default(), 42);
+        let mut b = Game::with_seed(24, 19, &Settings::default(), 42);
+        for game in [&mut a, &mut b] {
+            game.countdown = None;
+            game.autopilot = true;
+        }
+        for _ in 0..300 {
+            assert!(a.apple == b.apple);
+            a.step();
+            b.step();
+        }
+        assert!(a.score > 0);
+        assert_eq!(a.score, b.score);
+    }
+
+    #[test]
+    fn versus_head_on_collision_is_mutual() {
+        let settings = Settings {
+            versus: true,
+            ..Settings::default()
+        };
+        let mut game = Game::new(24, 19, &settings);
+        game.countdown = None;
+        game.apple = Point { x: 0, y: 0 };
+        let p1 = vec![
+            Point { x: 8, y: 4 },
+            Point { x: 7, y: 4 },
+            Point { x: 6, y: 4 },
+        ];
+        let p2 = vec![
+            Point { x: 10, y: 4 },
+            Point { x: 11, y: 4 },
+            Point { x: 12, y: 4 },
+        ];
+        game.occupied = p1.iter().chain(p2.iter()).copied().collect();
+        game.snakes[0].body = p1;
+        game.snakes[1].body = p2;
+
+        // Both heads move into (9, 4) on the same tick
+        game.step();
+        assert!(game.game_over);
+        assert!(game.winner.is_none());
+        assert!(!game.snakes[0].alive && !game.snakes[1].alive);
+    }
+
+    #[test]
+    fn reaching_the_target_score_wins_even_on_a_crash() {
+        let mut game = Game::from_board(20, 10, &Settings::default(), 1);
+        game.countdown = None;
+        set_snake(
+            &mut game,
+            vec![
+                Point { x: 5, y: 5 },
+                Point { x: 4, y: 5 },
+                Point { x: 3, y: 5 },
+            ],
+        );
+        game.target_score = Some(2);
+        game.apple = Point { x: 6, y: 5 };
+        game.step();
+        assert_eq!(game.score, 1);
+        assert!(!game.won && !game.game_over);
+
+        // Player two hits the wall on the tick player one eats the winner
+        let settings = Settings {
+            versus: true,
+            ..Settings::default()
+        };
+        let mut game = Game::new(24, 19, &settings);
+        game.countdown = None;
+        game.target_score = Some(1);
+        let p1 = vec![
+            Point { x: 8, y: 4 },
+            Point { x: 7, y: 4 },
+            Point { x: 6, y: 4 },
+        ];
+        let p2 = vec![
+            Point { x: 2, y: 0 },
+            Point { x: 2, y: 1 },
+            Point { x: 2, y: 2 },
+        ];
+        game.occupied = p1.iter().chain(p2.iter()).copied().collect();
+        game.snakes[0].body = p1;
+        game.snakes[1].body = p2;
+        game.snakes[1].dir = DirectionEnum::Up;
+        game.apple = Point { x: 9, y: 4 };
+        game.step();
+        assert!(!game.snakes[1].alive);
+        assert!(game.won && game.game_over);
+    }
+
+    #[test]
+    fn wall_presets_keep_clear_of_the_snake_and_apples() {
+        for preset in [WallPreset::Cross, WallPreset::BorderGap] {
+            let settings = Settings {
+                wall_preset: preset,
+                ..Settings::default()
+            };
+            let mut game = Game::from_board(30, 16, &settings, 3);
+            assert!(!game.walls.is_empty());
+            let snake = &game.snakes[0];
+            assert!(snake.body.iter().all(|p| !game.walls.contains(p)));
+            let ahead = game.neighbor(snake.head(), snake.dir).unwrap();
+            assert!(!game.walls.contains(&ahead));
+            for _ in 0..50 {
+                assert!(!game.walls.contains(&game.apple));
+                game.place_apple();
+            }
+        }
+
+        // The cross sits on the center, where the snake would start
+        let cross = WallPreset::Cross.cells(30, 16);
+        assert!(cross.contains(&Point { x: 15, y: 8 }));
+        let border = WallPreset::BorderGap.cells(30, 16);
+        assert!(border.contains(&Point { x: 0, y: 0 }));
+        assert!(!border.contains(&Point { x: 15, y: 0 }));
+        assert!(!border.contains(&Point { x: 0, y: 8 }));
+        assert!(!border.contains(&Point { x: 5, y: 5 }));
+    }
+
+    #[test]
+    fn extra_apples_are_eaten_and_replaced_one_at_a_time() {
+        let settings = Settings {
+            apple_count: 4,
+            ..Settings::default()
+        };
+        let mut game = Game::from_board(20, 10, &settings, 5);
+        game.countdown = None;
+        assert_eq!(game.apples.len(), 3);
+        let mut cells: Vec<Point> = game.apples.clone();
+        cells.push(game.apple);
+        for (k, p) in cells.iter().enumerate() {
+            assert!(!game.occupied.contains(p));
+            assert!(cells[k + 1..].iter().all(|q| q != p));
+        }
+
+        // Put an extra apple right ahead of the snake
+        let snake = &game.snakes[0];
+        let ahead = game.neighbor(snake.head(), snake.dir).unwrap();
+        game.apples[0] = ahead;
+        game.apple = Point { x: 0, y: 0 };
+        let others = game.apples[1..].to_vec();
+        game.step();
+        assert_eq!(game.score, 1);
+        assert!(game.apple == Point { x: 0, y: 0 });
+        assert_eq!(game.apples.len(), 3);
+        assert!(!game.apples.contains(&ahead));
+        assert!(others.iter().all(|p| game.apples.contains(p)));
+    }
+
+    #[test]
+    fn apple_hints_leave_the_random_stream_alone() {
+        let play = |apple_hints| {
+            let settings = Settings {
+                apple_hints,
+                ..Settings::default()
+            };
+            let mut game = Game::from_board(40, 20, &settings, 11);
+            game.countdown = None;
+            let mut apples = Vec::new();
+            for _ in 0..6 {
+                // Feed the snake its next apple straight away
+                let snake = &game.snakes[0];
+                let hint = game.next_apple;
+                game.apple = game.neighbor(snake.head(), snake.dir).unwrap();
+                game.step();
+                if apple_hints {
+                    assert!(hint == Some(game.apple));
+                }
+                apples.push((game.apple.x, game.apple.y));
+            }
+            (apples, game.rng.gen_range(0..u64::MAX))
+        };
+        assert_eq!(play(true), play(false));
+    }
+
+    #[test]
+    fn undo_then_stepping_again_repeats_the_step() {
+        let settings = Settings {
+            portals: true,
+            apple_hints: true,
+            ..Settings::default()
+        };
+        let mut recorded = Game::from_board(30, 12, &settings, 8);
+        recorded.countdown = None;
+        for _ in 0..8 {
+            recorded.step();
+        }
+        let replay = Replay::parse(&recorded.replay().to_text()).unwrap();
+
+        let mut game = Game::from_board(30, 12, &settings, 8);
+        game.countdown = None;
+        game.trail = Some(VecDeque::new());
+        game.ghost = Some(Box::new(Ghost::new(replay)));
+        game.keep_history();
+        game.step();
+        game.step();
+        let snake = &game.snakes[0];
+        game.apple = game.neighbor(snake.head(), snake.dir).unwrap();
+
+        let state = |g: &Game| {
+            let xy = |p: &Point| (p.x, p.y);
+            (
+                g.snakes[0].body.iter().map(xy).collect::<Vec<_>>(),
+                (xy(&g.apple), g.next_apple.as_ref().map(xy)),
+                g.portals.map(|(a, b)| (xy(&a), xy(&b))),
+                g.trail
+                    .iter()
+                    .flatten()
+                    .map(|(p, life)| (xy(p), *life))
+                    .collect::<Vec<_>>(),
+                g.ghost
+                    .as_ref()
+                    .and_then(|ghost| ghost.body())
+                    .map(|b| b.iter().map(xy).collect::<Vec<_>>()),
+                (g.score, g.ticks, g.combo, g.bullet_ticks),
+                g.rng.clone().gen_range(0..u64::MAX),
+            )
+        };
+        let before = state(&game);
+        game.step();
+        assert_eq!(game.score, 1);
+        let after = state(&game);
+        assert!(game.undo());
+        assert_eq!(state(&game), before);
+        game.step();
+        assert_eq!(state(&game), after);
+    }
+
+    #[test]
+    fn practice_overlaps_keep_the_body_occupied() {
+        let mut game = Game::from_board(20, 10, &Settings::default(), 1);
+        game.countdown = None;
+        game.apple = Point { x: 0, y: 0 };
+        set_snake(
+            &mut game,
+            (4..=10).rev().map(|x| Point { x, y: 5 }).collect(),
+        );
+        game.snakes[0].dir = DirectionEnum::Right;
+        game.toggle_invincible();
+
+        // Loop back up through the body, then carry on past it
+        for d in [
+            DirectionEnum::Down,
+            DirectionEnum::Left,
+            DirectionEnum::Up,
+            DirectionEnum::Up,
+        ] {
+            game.set_direction(0, d);
+            game.step();
+        }
+        game.toggle_invincible();
+        for _ in 0..4 {
+            game.step();
+            let body: HashSet<Point> = game.snakes[0].body.iter().copied().collect();
+            assert!(game.occupied == body);
+        }
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn smooth_tick_duration_decreases_to_floor() {
+        for difficulty in Difficulty::ALL {
+            let mut settings = Settings {
+                smooth_speed: true,
+                ..Settings::default()
+            };
+            settings.set_difficulty(difficulty);
+            let mut game = Game::new(24, 19, &settings);
+            let mut prev = game.tick_duration();
+            for score in 0..1000 {
+                game.score = score;
+                let tick = game.tick_duration();
+                assert!(tick <= prev);
+                assert!(tick >= Duration::from_millis(MIN_TICK_MS));
+                prev = tick;
+            }
+            assert_eq!(prev, Duration::from_millis(MIN_TICK_MS));
+        }
+    }
+
+    #[test]
+    fn walls_never_spawn_on_occupied_cells() {
+        let settings = Settings {
+            closing_walls: true,
+            ..Settings::default()
+        };
+        let mut game = Game::with_seed(24, 19, &settings, 7);
+        game.countdown = None;
+        // A long snake zigzagging across the top rows, head at (19, 4)
+        let mut snake: Vec<Point> = (0..5)
+            .flat_map(|y| {
+                let row: Vec<Point> = (0..20).map(|x| Point { x, y }).collect();
+                if y % 2 == 0 {
+                    row
+                } else {
+                    row.into_iter().rev().collect()
+                }
+            })
+            .collect();
+        snake.reverse();
+        set_snake(&mut game, snake);
+        game.snakes[0].dir = DirectionEnum::Down;
+        while game.spawn_wall() {}
+        assert!(!game.walls.is_empty());
+        for wall in &game.walls {
+            assert!(!game.occupied.contains(wall));
+            assert!(*wall != game.apple);
+        }
+    }
+
+    #[test]
+    fn game_state_serializes_to_json() {
+        let mut game = wrap_game(
+            vec![
+                Point { x: 2, y: 3 },
+                Point { x: 1, y: 3 },
+                Point { x: 0, y: 3 },
+            ],
+            DirectionEnum::Right,
+        );
+        game.score = 7;
+        game.level = 2;
+        assert_eq!(
+            game.to_json(),
+            "{\"width\":20,\"height\":10,\"score\":7,\"level\":2,\"game_over\":false,\
+             \"apple\":{\"x\":10,\"y\":5},\"snakes\":[{\"direction\":\"right\",\
+             \"alive\":true,\"score\":0,\"body\":[{\"x\":2,\"y\":3},{\"x\":1,\"y\":3},\
+             {\"x\":0,\"y\":3}]}]}"
+        );
+    }
+}
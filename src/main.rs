@@ -1,276 +1,1329 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    cursor::Show,
+    event::{
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind,
+    },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        self, EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode,
+        enable_raw_mode,
+    },
 };
-use rand::{Rng, rngs::ThreadRng};
 use ratatui::{
     Frame, Terminal,
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
+    buffer::{Buffer, Cell},
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
+use snake_game::*;
 use std::{
-    io,
+    collections::{HashMap, VecDeque},
+    fs,
+    io::{self, BufWriter, Write},
+    panic,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+    },
+    thread,
     time::{Duration, Instant},
 };
 
-/// Represents a position (x, y) on the board
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct Point {
-    x: u16,
-    y: u16,
+/// Time each countdown number stays on screen
+const COUNTDOWN_STEP: Duration = Duration::from_secs(1);
+/// How long one press of the boost key lasts
+const BOOST_DURATION: Duration = Duration::from_millis(1500);
+/// Fog radius used when fog of war is switched on from the menu
+const DEFAULT_FOG_RADIUS: u16 = 5;
+/// Board fill fraction from which the fill bar turns red
+const FILL_WARNING: f64 = 0.9;
+/// Cells in the header's fill bar
+const FILL_BAR_WIDTH: usize = 20;
+/// Frames kept in the rolling FPS window
+const FPS_WINDOW: usize = 60;
+/// Body colors cycled through by the rainbow snake, one per segment
+const RAINBOW: [Color; 6] = [
+    Color::Red,
+    Color::Yellow,
+    Color::Green,
+    Color::Cyan,
+    Color::Blue,
+    Color::Magenta,
+];
+/// Largest minimap drawn for boards bigger than the screen, in cells
+const MINIMAP_WIDTH: u16 = 24;
+const MINIMAP_HEIGHT: u16 = 8;
+/// Announcements kept in the `--text-mode` log
+const TEXT_LOG_LINES: usize = 100;
+/// How long a crashed snake takes to be eaten away after a game over
+const DEATH_ANIMATION: Duration = Duration::from_millis(1000);
+/// Longest the game screen goes without a redraw, so clocks and banners
+/// keep moving while nothing else changes
+const IDLE_REDRAW: Duration = Duration::from_millis(100);
+/// How long a pulsing apple stays bright, then dim
+const APPLE_PULSE: Duration = Duration::from_millis(400);
+/// Time between moves of the self-playing game behind the menu
+const DEMO_TICK: Duration = Duration::from_millis(90);
+/// Smallest terminal a game will start in
+const MIN_TERMINAL_WIDTH: u16 = 20;
+const MIN_TERMINAL_HEIGHT: u16 = 10;
+/// How long the level-up banner stays up
+const LEVEL_BANNER: Duration = Duration::from_millis(500);
+/// How long the wall-grace warning stays up
+const GRACE_WARNING: Duration = Duration::from_millis(500);
+
+/// Game events that make a sound when `--sound` is on
+#[derive(Clone, Copy)]
+pub enum SoundEvent {
+    Eat,
+    LevelUp,
+    Death,
+}
+
+impl SoundEvent {
+    /// Each event rings the bell a distinct number of times
+    pub fn bells(self) -> u32 {
+        match self {
+            SoundEvent::Eat => 1,
+            SoundEvent::LevelUp => 2,
+            SoundEvent::Death => 3,
+        }
+    }
+}
+
+/// Starts a background player that rings the terminal bell for each event
+///
+/// Playback runs on its own thread so the game loop never waits on it, and
+/// write errors are ignored so sound can never take the game down.
+pub fn spawn_sound_player() -> Sender<SoundEvent> {
+    let (tx, rx) = mpsc::channel::<SoundEvent>();
+    thread::spawn(move || {
+        for event in rx {
+            for i in 0..event.bells() {
+                if i > 0 {
+                    thread::sleep(Duration::from_millis(150));
+                }
+                let mut out = io::stdout();
+                let _ = out.write_all(b"\x07").and_then(|_| out.flush());
+            }
+        }
+    });
+    tx
+}
+
+/// Reacts to a step's events with sounds and banners
+fn handle_events(game: &mut Game, sound: Option<&Sender<SoundEvent>>) {
+    let events = game.drain_events();
+    let leveled_up = events.iter().any(|e| matches!(e, GameEvent::LevelUp(_)));
+    for event in events {
+        let sound_event = match event {
+            // A level-up plays its own sound instead of the eat sound
+            GameEvent::AppleEaten { .. } if !leveled_up => Some(SoundEvent::Eat),
+            GameEvent::LevelUp(_) => {
+                game.level_banner_until = Some(Instant::now() + LEVEL_BANNER);
+                Some(SoundEvent::LevelUp)
+            }
+            GameEvent::WallGrace { .. } => {
+                game.grace_warning_until = Some(Instant::now() + GRACE_WARNING);
+                None
+            }
+            GameEvent::LifeLost { .. } | GameEvent::GameOver => Some(SoundEvent::Death),
+            _ => None,
+        };
+        if let (Some(tx), Some(sound_event)) = (sound, sound_event) {
+            let _ = tx.send(sound_event);
+        }
+    }
+}
+
+/// Waits up to `timeout` for the next input event
+///
+/// Key releases are dropped: terminals that report them (Windows, or the
+/// kitty keyboard protocol) would otherwise act on every key twice.
+fn poll_event(timeout: Duration) -> io::Result<Option<Event>> {
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+    Ok(match event::read()? {
+        Event::Key(KeyEvent {
+            kind: KeyEventKind::Release,
+            ..
+        }) => None,
+        other => Some(other),
+    })
 }
 
-/// Snake movement directions
+/// Which screen the app is currently showing
 #[derive(Clone, Copy, PartialEq)]
-enum DirectionEnum {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-/// Main game state
-struct Game {
-    snake: Vec<Point>,
-    dir: DirectionEnum,
-    next_dir: DirectionEnum,
-    apple: Point,
-    rng: ThreadRng,
-    score: u32,
-    width: u16,
-    height: u16,
-    game_over: bool,
-    level: u32,
-    base_tick_ms: u64,
+enum AppScreen {
+    Menu,
+    Settings,
+    Leaderboard,
+    Game,
 }
 
-impl Game {
-    /// Initializes a new game session
-    fn new(area: Rect) -> Self {
-        let width = area.width.saturating_sub(2).max(10);
-        let height = area.height.saturating_sub(4).max(5);
-        let rng = rand::thread_rng();
-
-        let mid_x = width / 2;
-        let mid_y = height / 2;
-        let snake = vec![
-            Point { x: mid_x, y: mid_y },
-            Point {
-                x: mid_x.saturating_sub(1),
-                y: mid_y,
-            },
-            Point {
-                x: mid_x.saturating_sub(2),
-                y: mid_y,
-            },
+/// Something a key can be bound to during a game
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Move(DirectionEnum),
+    Quit,
+    Restart,
+    Menu,
+    Undo,
+    Pause,
+    Autopilot,
+    Boost,
+    Faster,
+    Slower,
+    Practice,
+    Theme,
+    Help,
+}
+
+impl Action {
+    const ALL: [Action; 16] = [
+        Action::Move(DirectionEnum::Up),
+        Action::Move(DirectionEnum::Down),
+        Action::Move(DirectionEnum::Left),
+        Action::Move(DirectionEnum::Right),
+        Action::Quit,
+        Action::Restart,
+        Action::Menu,
+        Action::Undo,
+        Action::Pause,
+        Action::Autopilot,
+        Action::Boost,
+        Action::Faster,
+        Action::Slower,
+        Action::Practice,
+        Action::Theme,
+        Action::Help,
+    ];
+
+    /// Name used in the key bindings file
+    fn name(self) -> &'static str {
+        match self {
+            Action::Move(d) => d.name(),
+            Action::Quit => "quit",
+            Action::Restart => "restart",
+            Action::Menu => "menu",
+            Action::Undo => "undo",
+            Action::Pause => "pause",
+            Action::Autopilot => "autopilot",
+            Action::Boost => "boost",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::Practice => "practice",
+            Action::Theme => "theme",
+            Action::Help => "help",
+        }
+    }
+
+    /// What the action does, for the help overlay
+    fn describe(self) -> &'static str {
+        match self {
+            Action::Move(DirectionEnum::Up) => "move up",
+            Action::Move(DirectionEnum::Down) => "move down",
+            Action::Move(DirectionEnum::Left) => "move left",
+            Action::Move(DirectionEnum::Right) => "move right",
+            Action::Quit => "quit",
+            Action::Restart => "restart",
+            Action::Menu => "main menu",
+            Action::Undo => "undo a step (--debug)",
+            Action::Pause => "pause",
+            Action::Autopilot => "autopilot",
+            Action::Boost => "speed boost",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::Practice => "practice mode",
+            Action::Theme => "next theme",
+            Action::Help => "this help",
+        }
+    }
+
+    /// Parses a name written by [`Action::name`]
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Parses a key as written in the bindings file: a single character, or a
+/// special key name such as `Up`, `Space` or `Esc` (case-insensitive)
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    Some(match name.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        _ => return None,
+    })
+}
+
+/// Short label for a key, as shown in the controls hint
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_uppercase().to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// In-game key bindings, loaded from `keys.toml` in the data directory
+///
+/// The file holds one `action = "key"` (or `action = ["key", ...]`) line per
+/// action to rebind; actions left out keep their default keys. The arrow
+/// keys are not part of the map: they always steer player two in versus mode
+/// and player one otherwise, unless bound to an action here.
+struct KeyMap {
+    bindings: Vec<(KeyCode, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = vec![
+            (KeyCode::Char('w'), Action::Move(DirectionEnum::Up)),
+            (KeyCode::Char('s'), Action::Move(DirectionEnum::Down)),
+            (KeyCode::Char('a'), Action::Move(DirectionEnum::Left)),
+            (KeyCode::Char('d'), Action::Move(DirectionEnum::Right)),
+            (KeyCode::Char('k'), Action::Move(DirectionEnum::Up)),
+            (KeyCode::Char('K'), Action::Move(DirectionEnum::Up)),
+            (KeyCode::Char('j'), Action::Move(DirectionEnum::Down)),
+            (KeyCode::Char('J'), Action::Move(DirectionEnum::Down)),
+            (KeyCode::Char('h'), Action::Move(DirectionEnum::Left)),
+            (KeyCode::Char('H'), Action::Move(DirectionEnum::Left)),
+            (KeyCode::Char('l'), Action::Move(DirectionEnum::Right)),
+            (KeyCode::Char('L'), Action::Move(DirectionEnum::Right)),
+            (KeyCode::Char('q'), Action::Quit),
+            (KeyCode::Char('Q'), Action::Quit),
+            (KeyCode::Char('r'), Action::Restart),
+            (KeyCode::Char('R'), Action::Restart),
+            (KeyCode::Char('m'), Action::Menu),
+            (KeyCode::Char('M'), Action::Menu),
+            (KeyCode::Char('u'), Action::Undo),
+            (KeyCode::Char('U'), Action::Undo),
+            (KeyCode::Char(' '), Action::Pause),
+            (KeyCode::Char('p'), Action::Autopilot),
+            (KeyCode::Char('P'), Action::Autopilot),
+            (KeyCode::Char('e'), Action::Boost),
+            (KeyCode::Char('E'), Action::Boost),
+            (KeyCode::Char('+'), Action::Faster),
+            (KeyCode::Char('='), Action::Faster),
+            (KeyCode::Char('-'), Action::Slower),
+            (KeyCode::Char('_'), Action::Slower),
+            (KeyCode::Char('i'), Action::Practice),
+            (KeyCode::Char('I'), Action::Practice),
+            (KeyCode::Char('t'), Action::Theme),
+            (KeyCode::Char('T'), Action::Theme),
+            (KeyCode::Char('?'), Action::Help),
         ];
+        Self { bindings }
+    }
+}
 
-        let mut g = Self {
-            snake,
-            dir: DirectionEnum::Right,
-            next_dir: DirectionEnum::Right,
-            apple: Point { x: 0, y: 0 },
-            rng,
-            score: 0,
-            width,
-            height,
-            game_over: false,
-            level: 1,
-            base_tick_ms: 160,
-        };
-        g.place_apple();
-        g
+impl KeyMap {
+    /// Action bound to a key, if any
+    fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| *k == code)
+            .map(|&(_, a)| a)
     }
 
-    /// Places a new apple randomly on the board
-    fn place_apple(&mut self) {
-        for _ in 0..1000 {
-            let x = self.rng.gen_range(0..self.width);
-            let y = self.rng.gen_range(0..self.height);
-            let cand = Point { x, y };
-            if !self.snake.iter().any(|s| s.x == x && s.y == y) {
-                self.apple = cand;
-                return;
+    /// Labels of the keys bound to an action, for on-screen hints
+    ///
+    /// Upper and lower case bindings of a letter share one label.
+    fn labels(&self, action: Action) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for &(k, a) in &self.bindings {
+            let label = key_label(k);
+            if a == action && !labels.contains(&label) {
+                labels.push(label);
             }
         }
-        self.apple = Point { x: 1, y: 1 };
+        labels
     }
 
-    /// Changes snake direction (no reverse allowed)
-    fn set_direction(&mut self, d: DirectionEnum) {
-        let is_reverse = matches!(
-            (self.dir, d),
-            (DirectionEnum::Up, DirectionEnum::Down)
-                | (DirectionEnum::Down, DirectionEnum::Up)
-                | (DirectionEnum::Left, DirectionEnum::Right)
-                | (DirectionEnum::Right, DirectionEnum::Left)
-        );
-        if !is_reverse {
-            self.next_dir = d;
+    /// Label of the first key bound to an action
+    fn label(&self, action: Action) -> String {
+        self.labels(action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "?".to_string())
+    }
+
+    /// Parses a bindings file on top of the defaults
+    ///
+    /// Invalid lines are skipped (their action keeps its default keys) and
+    /// returned as warnings. A key named in a line is taken from whichever
+    /// action had it by default; two lines naming one key reject the file.
+    fn parse(text: &str) -> Result<(Self, Vec<String>), String> {
+        let mut map = Self::default();
+        let mut warnings = Vec::new();
+        let mut rebound: Vec<Action> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match Self::parse_line(line) {
+                Ok((action, keys)) => {
+                    if let Some(&(key, other)) = map
+                        .bindings
+                        .iter()
+                        .find(|&&(k, a)| a != action && rebound.contains(&a) && keys.contains(&k))
+                    {
+                        return Err(format!(
+                            "{} is bound to both {} and {}",
+                            key_label(key),
+                            other.name(),
+                            action.name()
+                        ));
+                    }
+                    map.bindings
+                        .retain(|&(k, a)| a != action && !keys.contains(&k));
+                    map.bindings.extend(keys.into_iter().map(|k| (k, action)));
+                    rebound.push(action);
+                }
+                Err(err) => warnings.push(format!("line {}: {}", n + 1, err)),
+            }
         }
+        Ok((map, warnings))
     }
 
-    /// Game tick — moves snake, checks collisions, updates score
-    fn step(&mut self) {
-        if self.game_over {
-            return;
+    /// Parses one `action = "key"` or `action = ["key", ...]` line
+    fn parse_line(line: &str) -> Result<(Action, Vec<KeyCode>), String> {
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected `action = \"key\"`: {}", line))?;
+        let action = Action::from_name(name.trim())
+            .ok_or_else(|| format!("unknown action: {}", name.trim()))?;
+        let value = value.trim();
+        let list = value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value);
+        let keys = list
+            .split(',')
+            .map(|k| {
+                let k = k.trim();
+                k.strip_prefix('"')
+                    .and_then(|k| k.strip_suffix('"'))
+                    .and_then(parse_key)
+                    .ok_or_else(|| format!("invalid key for {}: {}", action.name(), k))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((action, keys))
+    }
+
+    /// Loads the bindings file, using the defaults when it doesn't exist
+    fn load(path: &Path) -> Result<(Self, Vec<String>), String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok((Self::default(), Vec::new())),
+            Err(err) => Err(err.to_string()),
         }
-        self.dir = self.next_dir;
-        let head = self.snake[0];
-        let new_head = match self.dir {
-            DirectionEnum::Up => Point {
-                x: head.x,
-                y: head.y.saturating_sub(1),
-            },
-            DirectionEnum::Down => Point {
-                x: head.x,
-                y: head.y.saturating_add(1),
-            },
-            DirectionEnum::Left => Point {
-                x: head.x.saturating_sub(1),
-                y: head.y,
-            },
-            DirectionEnum::Right => Point {
-                x: head.x.saturating_add(1),
-                y: head.y,
-            },
-        };
+    }
+}
 
-        // Check collisions with borders or itself
-        if new_head.x >= self.width || new_head.y >= self.height {
-            self.game_over = true;
-            return;
+/// Where custom key bindings are read from
+fn keys_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("keys.toml"))
+}
+
+/// Direction for an arrow key
+fn arrow_direction(code: KeyCode) -> Option<DirectionEnum> {
+    match code {
+        KeyCode::Up => Some(DirectionEnum::Up),
+        KeyCode::Down => Some(DirectionEnum::Down),
+        KeyCode::Left => Some(DirectionEnum::Left),
+        KeyCode::Right => Some(DirectionEnum::Right),
+        _ => None,
+    }
+}
+
+/// Render timestamps over a rolling window, for the `--debug` overlay
+#[derive(Default)]
+struct FrameStats {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameStats {
+    /// Notes that a frame was just rendered
+    fn record(&mut self) {
+        if self.frames.len() == FPS_WINDOW {
+            self.frames.pop_front();
         }
-        if self
-            .snake
-            .iter()
-            .any(|s| s.x == new_head.x && s.y == new_head.y)
-        {
-            self.game_over = true;
-            return;
+        self.frames.push_back(Instant::now());
+    }
+
+    /// Average frames per second across the window
+    fn fps(&self) -> f64 {
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.frames.len() - 1) as f64 / (*last - *first).as_secs_f64()
+            }
+            _ => 0.0,
         }
+    }
+}
 
-        // Move snake forward
-        self.snake.insert(0, new_head);
+/// Plain-text announcements shown instead of the board by `--text-mode`,
+/// for players using a screen reader
+#[derive(Default)]
+struct TextLog {
+    lines: VecDeque<String>,
+    /// Everything the last line reported except the head, which moves every
+    /// tick and would flood the log
+    last_state: String,
+}
 
-        // Check apple collision
-        if new_head.x == self.apple.x && new_head.y == self.apple.y {
-            self.score += 1;
-            if self.score % 5 == 0 {
-                self.level = 1 + (self.score / 5);
-            }
-            self.place_apple();
+impl TextLog {
+    /// Announces the game state if anything worth reading out has changed
+    fn update(&mut self, game: &Game) {
+        let snake = &game.snakes[0];
+        let state = if game.game_over {
+            format!("Game over, score {}", game.score)
+        } else if let Some(n) = game.countdown {
+            format!("Starting in {}", n)
+        } else if game.paused {
+            "Paused".to_string()
+        } else {
+            format!(
+                "Score {}, apple at {},{}, heading {}",
+                game.score,
+                game.apple.x,
+                game.apple.y,
+                snake.dir.name()
+            )
+        };
+        if state == self.last_state {
+            return;
+        }
+        let line = if game.game_over || game.countdown.is_some() || game.paused {
+            state.clone()
         } else {
-            self.snake.pop();
+            let head = snake.head();
+            state.replacen(
+                ", apple",
+                &format!(", head at {},{}, apple", head.x, head.y),
+                1,
+            )
+        };
+        if self.lines.len() == TEXT_LOG_LINES {
+            self.lines.pop_front();
         }
+        self.lines.push_back(line);
+        self.last_state = state;
     }
+}
 
-    /// Controls snake speed (faster with higher levels)
-    fn tick_duration(&self) -> Duration {
-        let reduce = (self.level - 1) as u64 * 10;
-        let ms = self.base_tick_ms.saturating_sub(reduce).max(40);
-        Duration::from_millis(ms)
-    }
+/// Draws the `--text-mode` log, newest line last
+fn draw_text_log<B: ratatui::backend::Backend>(f: &mut Frame<B>, area: Rect, log: &TextLog) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Snake - text mode");
+    let shown = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = log
+        .lines
+        .iter()
+        .skip(log.lines.len().saturating_sub(shown))
+        .map(|l| Line::from(l.as_str()))
+        .collect();
+    f.render_widget(Paragraph::new(lines).block(block), area);
 }
 
-/// Draws the main game screen
-fn draw_game<B: ratatui::backend::Backend>(f: &mut Frame<B>, game: &Game, area: Rect) {
-    let chunks = Layout::default()
+/// Splits the game screen into header, board and status rows
+fn game_chunks(area: Rect, spacing: Spacing) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
-        .margin(1)
+        .margin(spacing.margin())
         .constraints(
             [
-                Constraint::Length(3),
-                Constraint::Min(8),
-                Constraint::Length(2),
+                Constraint::Length(spacing.header_height()),
+                Constraint::Min(3),
+                Constraint::Length(spacing.status_height()),
             ]
             .as_ref(),
         )
-        .split(area);
+        .split(area)
+}
+
+/// Screen area of the board's cells, inside its border
+fn board_rect(area: Rect, spacing: Spacing) -> Rect {
+    Block::default()
+        .borders(Borders::ALL)
+        .inner(game_chunks(area, spacing)[1])
+}
+
+/// Whether a pulsing apple is in the dim half of its pulse, counted from
+/// the game's start
+fn apple_dimmed(game: &Game) -> bool {
+    game.settings.pulse_apple
+        && (game.start.elapsed().as_millis() / APPLE_PULSE.as_millis()) % 2 == 1
+}
+
+/// Time left until a pulsing apple next changes phase
+fn until_pulse(game: &Game) -> Duration {
+    if !game.settings.pulse_apple {
+        return Duration::MAX;
+    }
+    let into = game.start.elapsed().as_millis() % APPLE_PULSE.as_millis();
+    APPLE_PULSE.saturating_sub(Duration::from_millis(into as u64))
+}
+
+/// Score, length or both for the header, per player in versus mode
+fn header_score(game: &Game) -> String {
+    let show_score = game.settings.score_display != ScoreDisplay::Length;
+    let show_length = game.settings.score_display != ScoreDisplay::Score;
+    if game.is_versus() {
+        let player = |i: usize| {
+            let snake = &game.snakes[i];
+            match (show_score, show_length) {
+                (true, true) => format!("P{}: {} ({} long)", i + 1, snake.score, snake.body.len()),
+                (false, _) => format!("P{}: {} long", i + 1, snake.body.len()),
+                _ => format!("P{}: {}", i + 1, snake.score),
+            }
+        };
+        return format!("{}  {}", player(0), player(1));
+    }
+    let mut parts = Vec::new();
+    if show_score {
+        parts.push(match game.target {
+            Some(target) => format!("Score: {}/{}", game.score, target),
+            None => format!("Score: {}", game.score),
+        });
+    }
+    if show_length {
+        parts.push(format!("Length: {}", game.snakes[0].body.len()));
+    }
+    if show_score {
+        parts.push(format!("Best: {}", game.high_score.max(game.earned())));
+    }
+    parts.join("  ")
+}
+
+/// Columns the ruler's row numbers take up, including a space
+fn ruler_gutter(game: &Game) -> u16 {
+    ruler_width(game.height)
+}
+
+/// Part of the board area left for cells once the ruler, if on, takes its
+/// top row and left gutter
+fn board_cells(game: &Game, inner: Rect) -> Rect {
+    if !game.settings.ruler {
+        return inner;
+    }
+    let gutter = ruler_gutter(game).min(inner.width);
+    Rect {
+        x: inner.x + gutter,
+        y: inner.y + inner.height.min(1),
+        width: inner.width - gutter,
+        height: inner.height.saturating_sub(1),
+    }
+}
+
+/// Draws column numbers above and row numbers left of the cells in `cells`
+///
+/// Columns show their last digit, except every tenth which shows its tens
+/// digit in bold; square cells have room for two digits.
+fn draw_ruler<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    game: &Game,
+    theme: &Theme,
+    cells: Rect,
+    view: Rect,
+) {
+    if cells.y == 0 || cells.x == 0 {
+        return;
+    }
+    let dim = Style::default().fg(theme.border.tui());
+    let bold = dim.add_modifier(Modifier::BOLD);
+    let columns: Vec<Span> = (view.x..view.x + view.width)
+        .map(|x| {
+            if game.cell_width() > 1 {
+                Span::styled(
+                    format!("{:<1$}", x % 100, game.cell_width() as usize),
+                    if x % 10 == 0 { bold } else { dim },
+                )
+            } else if x % 10 == 0 {
+                Span::styled(((x / 10) % 10).to_string(), bold)
+            } else {
+                Span::styled((x % 10).to_string(), dim)
+            }
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(Line::from(columns)),
+        Rect {
+            y: cells.y - 1,
+            height: 1,
+            ..cells
+        },
+    );
+
+    let gutter = ruler_gutter(game);
+    let rows: Vec<Line> = (view.y..view.y + view.height)
+        .map(|y| {
+            let style = if y % 10 == 0 { bold } else { dim };
+            Line::from(Span::styled(
+                format!("{:>width$} ", y, width = gutter as usize - 1),
+                style,
+            ))
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(rows),
+        Rect {
+            x: cells.x.saturating_sub(gutter),
+            width: gutter.min(cells.x),
+            ..cells
+        },
+    );
+}
+
+/// Part of the board shown in `inner`, in board cells
+///
+/// Boards bigger than the screen scroll to keep player one's head centred,
+/// clamped so the view never runs past the board's edges.
+fn viewport(game: &Game, inner: Rect) -> Rect {
+    let width = (inner.width / game.cell_width()).min(game.width);
+    let height = inner.height.min(game.height);
+    let head = game.snakes[0].head();
+    let follow =
+        |head: u16, shown: u16, size: u16| head.saturating_sub(shown / 2).min(size - shown);
+    Rect {
+        x: follow(head.x, width, game.width),
+        y: follow(head.y, height, game.height),
+        width,
+        height,
+    }
+}
+
+/// Box-drawing piece joining a body segment to its neighbors in the given
+/// directions; a lone link is the tail's cap
+fn line_glyph(links: &[DirectionEnum], ascii: bool) -> Option<&'static str> {
+    let has = |d| links.contains(&d);
+    let (up, down, left, right) = (
+        has(DirectionEnum::Up),
+        has(DirectionEnum::Down),
+        has(DirectionEnum::Left),
+        has(DirectionEnum::Right),
+    );
+    let (glyph, ascii_glyph) = match (links.len(), up, down, left, right) {
+        (2, false, false, true, true) => ("─", "-"),
+        (2, true, true, false, false) => ("│", "|"),
+        (2, false, true, false, true) => ("┌", "+"),
+        (2, false, true, true, false) => ("┐", "+"),
+        (2, true, false, false, true) => ("└", "+"),
+        (2, true, false, true, false) => ("┘", "+"),
+        (1, _, _, false, true) => ("╶", "-"),
+        (1, _, _, true, false) => ("╴", "-"),
+        (1, true, false, _, _) => ("╵", "|"),
+        (1, false, true, _, _) => ("╷", "|"),
+        _ => return None,
+    };
+    Some(if ascii { ascii_glyph } else { glyph })
+}
+
+/// Draws a scaled-down copy of the whole board in the bottom-right corner,
+/// shading the part the main view shows
+fn draw_minimap<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    game: &Game,
+    theme: &Theme,
+    glyphs: &Glyphs,
+    inner: Rect,
+    view: Rect,
+) {
+    // Keep the minimap to about a third of the view so it never hides much
+    let max_width = MINIMAP_WIDTH.min(inner.width / 3).max(1);
+    let max_height = MINIMAP_HEIGHT.min(inner.height / 3).max(1);
+    let scale_x = game.width.div_ceil(max_width);
+    let scale_y = game.height.div_ceil(max_height);
+    let (width, height) = (game.width.div_ceil(scale_x), game.height.div_ceil(scale_y));
+    if width + 2 > inner.width || height + 2 > inner.height {
+        return;
+    }
+
+    // Each minimap cell shows the most important thing in its patch of board
+    const WALL: u8 = 1;
+    const SNAKE: u8 = 2;
+    const APPLE: u8 = 3;
+    let mut cells = vec![0u8; width as usize * height as usize];
+    let mut mark = |p: Point, what: u8| {
+        let i = (p.y / scale_y) as usize * width as usize + (p.x / scale_x) as usize;
+        cells[i] = cells[i].max(what);
+    };
+    for &p in &game.walls {
+        mark(p, WALL);
+    }
+    for &p in &game.occupied {
+        mark(p, SNAKE);
+    }
+    for &p in std::iter::once(&game.apple).chain(&game.apples) {
+        if game.is_lit(p) {
+            mark(p, APPLE);
+        }
+    }
+
+    let rows: Vec<Line> = (0..height)
+        .map(|my| {
+            let spans: Vec<Span> = (0..width)
+                .map(|mx| {
+                    let (x, y) = (mx * scale_x, my * scale_y);
+                    let in_view = x < view.x + view.width
+                        && x + scale_x > view.x
+                        && y < view.y + view.height
+                        && y + scale_y > view.y;
+                    let bg = if in_view {
+                        theme.grid.tui()
+                    } else {
+                        theme.background.tui()
+                    };
+                    let (ch, fg) = match cells[my as usize * width as usize + mx as usize] {
+                        APPLE => (glyphs.apple, theme.apple.tui()),
+                        SNAKE => (glyphs.body, theme.snake_body.tui()),
+                        WALL => (glyphs.wall, theme.border.tui()),
+                        _ => (" ", theme.border.tui()),
+                    };
+                    Span::styled(ch, Style::default().fg(fg).bg(bg))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    let rect = Rect {
+        x: inner.x + inner.width - width - 2,
+        y: inner.y + inner.height - height - 2,
+        width: width + 2,
+        height: height + 2,
+    };
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(rows).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border.tui())),
+        ),
+        rect,
+    );
+}
+
+/// Draws the main game screen
+fn draw_game<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    game: &Game,
+    theme: &Theme,
+    glyphs: &Glyphs,
+    keys: &KeyMap,
+    area: Rect,
+) {
+    let chunks = game_chunks(area, game.settings.spacing);
 
     // Header with score and level
     let title = Paragraph::new(Line::from(vec![
         Span::styled(
-            " Snake (Rust + ratatui) ",
-            Style::default().fg(Color::Yellow),
+            match &game.daily {
+                Some(date) => format!(" Daily Challenge — {} ", date),
+                None => " Snake (Rust + ratatui) ".to_string(),
+            },
+            Style::default().fg(theme.title.tui()),
+        ),
+        Span::raw("  "),
+        Span::styled(header_score(game), Style::default().fg(theme.score.tui())),
+        Span::raw("  "),
+        Span::styled(
+            match game.multiplier() {
+                1 => format!("Level: {}", game.level),
+                factor => format!("Level: {} (x{})", game.level, factor),
+            },
+            Style::default().fg(theme.level.tui()),
         ),
         Span::raw("  "),
+        if game.settings.lives > 1 {
+            let heart = if glyphs.ascii { "<3" } else { "♥" };
+            Span::styled(
+                format!("{}  ", heart.repeat(game.lives as usize)),
+                Style::default().fg(theme.apple.tui()),
+            )
+        } else {
+            Span::raw("")
+        },
+        match game.magnet_remaining() {
+            Some(left) => Span::styled(
+                format!("{} {}s  ", glyphs.magnet, left.as_secs() + 1),
+                Style::default()
+                    .fg(theme.special_apple.tui())
+                    .add_modifier(Modifier::BOLD),
+            ),
+            None => Span::raw(""),
+        },
+        match game.active_combo() {
+            Some(combo) => {
+                let style = Style::default().fg(theme.score.tui());
+                Span::styled(
+                    format!("Combo x{}  ", combo),
+                    if game.combo_flashing() {
+                        style.add_modifier(Modifier::BOLD | Modifier::REVERSED)
+                    } else {
+                        style
+                    },
+                )
+            }
+            None => Span::raw(""),
+        },
         Span::styled(
-            format!("Score: {}", game.score),
-            Style::default().fg(Color::LightGreen),
+            game.settings.difficulty.name(),
+            Style::default().fg(theme.level.tui()),
         ),
         Span::raw("  "),
         Span::styled(
-            format!("Level: {}", game.level),
-            Style::default().fg(Color::Cyan),
+            match game.time_left() {
+                Some(left) => {
+                    // Round up so the clock reads 0:00 only when time is up
+                    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+                    format!("Time: {}:{:02}  ", secs / 60, secs % 60)
+                }
+                None if game.speedrun_goal.is_some() => {
+                    format!("Run: {}  ", format_run_time(game.run_time()))
+                }
+                None => String::new(),
+            },
+            Style::default().fg(theme.score.tui()),
+        ),
+        // Latest split against the personal best: green when ahead
+        match game.split_delta() {
+            Some((_, delta)) => Span::styled(
+                format!("{}  ", format_delta(delta)),
+                Style::default().fg(if delta <= 0 {
+                    Color::Green
+                } else {
+                    theme.alert.tui()
+                }),
+            ),
+            None => Span::raw(""),
+        },
+        Span::styled(
+            if game.playback {
+                "REPLAY"
+            } else if game.autopilot {
+                "AUTO"
+            } else if game.boosting() {
+                "BOOST"
+            } else {
+                ""
+            },
+            Style::default()
+                .fg(theme.alert.tui())
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            if game.practice { " PRACTICE" } else { "" },
+            Style::default()
+                .fg(theme.alert.tui())
+                .add_modifier(Modifier::BOLD),
         ),
     ]))
     .alignment(Alignment::Left);
     f.render_widget(title, chunks[0]);
 
+    // Board fill bar on the header's second line, red when nearly full
+    let fill = game.fill_ratio();
+    let filled = ((fill * FILL_BAR_WIDTH as f64).round() as usize).min(FILL_BAR_WIDTH);
+    let fill_style = if fill >= FILL_WARNING {
+        Style::default()
+            .fg(theme.alert.tui())
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.score.tui())
+    };
+    let fill_bar = Paragraph::new(Line::from(vec![
+        Span::raw(" Fill ["),
+        Span::styled("#".repeat(filled), fill_style),
+        Span::raw("-".repeat(FILL_BAR_WIDTH - filled)),
+        Span::raw("] "),
+        Span::styled(format!("{:.0}%", fill * 100.0), fill_style),
+    ]));
+    if chunks[0].height > 1 {
+        let fill_area = Rect {
+            y: chunks[0].y + 1,
+            height: 1,
+            ..chunks[0]
+        };
+        f.render_widget(fill_bar, fill_area);
+    }
+
     // Game board area
+    // The board title flashes the new level briefly, leaving the cells visible
+    let board_title = if game
+        .grace_warning_until
+        .is_some_and(|until| Instant::now() < until)
+    {
+        Span::styled(
+            " WALL! TURN! ",
+            Style::default()
+                .fg(theme.alert.tui())
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )
+    } else if game
+        .level_banner_until
+        .is_some_and(|until| Instant::now() < until)
+    {
+        Span::styled(
+            format!(" LEVEL {}! ", game.level),
+            Style::default()
+                .fg(theme.alert.tui())
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )
+    } else {
+        Span::styled(" Game ", Style::default().fg(theme.border.tui()))
+    };
     let board_block = Block::default()
         .borders(Borders::ALL)
-        .title(Span::styled(" Game ", Style::default().fg(Color::Magenta)));
+        .border_style(Style::default().fg(theme.border_color(game.score, truecolor()).tui()))
+        .title(board_title);
     let inner = board_block.inner(chunks[1]);
     f.render_widget(board_block, chunks[1]);
 
-    // Render snake and apple
+    // Render snake and apple, scrolling when the board outgrows the screen
+    let inner = board_cells(game, inner);
+    let view = viewport(game, inner);
+    if game.settings.ruler {
+        draw_ruler(f, game, theme, inner, view);
+    }
+    let grid = game.settings.grid || theme.pixel_grid;
+    // Indexed once per frame so long snakes don't cost a body scan per cell
+    let mut segments: HashMap<Point, (usize, usize)> = HashMap::new();
+    for (player, snake) in game.snakes.iter().enumerate() {
+        // The death animation eats crashed snakes from the head down
+        let hidden = if snake.alive { 0 } else { game.vanished };
+        for (i, &p) in snake.body.iter().enumerate().skip(hidden) {
+            segments.entry(p).or_insert((player, i));
+        }
+    }
+    // The best run's ghost shows only where nothing live is drawn
+    let ghost: HashMap<Point, usize> = game
+        .ghost
+        .as_ref()
+        .and_then(|g| g.body())
+        .map(|body| body.iter().enumerate().map(|(i, &p)| (p, i)).collect())
+        .unwrap_or_default();
     let mut rows: Vec<Line> = Vec::new();
-    for y in 0..game.height {
+    for y in view.y..view.y + view.height {
         let mut spans = Vec::new();
-        for x in 0..game.width {
-            let (ch, style) = if x == game.apple.x && y == game.apple.y {
+        for x in view.x..view.x + view.width {
+            // Empty cells alternate two dim shades when the grid is on
+            let cell_bg = if grid && (x + y) % 2 == 1 {
+                theme.grid.tui()
+            } else {
+                theme.background.tui()
+            };
+            // Wide cells are filled by solid glyphs; the rest are drawn
+            // `glyph_width` times and padded so apples and heads line up
+            // with the body
+            let columns = game.cell_width() as usize;
+            let cell = |ch: &str, solid: bool| {
+                if solid {
+                    ch.repeat(columns)
+                } else {
+                    format!("{:<columns$}", ch.repeat(game.settings.cell_width as usize))
+                }
+            };
+            // Fog hides everything outside the lit radius, snake included
+            if !game.is_lit(Point { x, y }) {
+                spans.push(Span::styled(cell(" ", true), Style::default().bg(cell_bg)));
+                continue;
+            }
+            // Closed-off border ring
+            if !game.in_arena(Point { x, y }) {
+                spans.push(Span::styled(
+                    cell(glyphs.closed, true),
+                    Style::default().fg(theme.border.tui()).bg(cell_bg),
+                ));
+                continue;
+            }
+            let on_special = game
+                .special
+                .as_ref()
+                .is_some_and(|s| s.pos.x == x && s.pos.y == y);
+            // Set for line bodies whose piece runs on into the next cell, so
+            // wide cells draw the line across every column
+            let mut joins_right = false;
+            // Line pieces are drawn once, never repeated like other glyphs
+            let mut line_piece = false;
+            let (ch, style, solid) = if game.is_apple(Point { x, y }) {
+                let pulse = if apple_dimmed(game) {
+                    Modifier::DIM
+                } else {
+                    Modifier::BOLD
+                };
                 (
-                    "@",
-                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    glyphs.apple,
+                    Style::default().fg(theme.apple.tui()).add_modifier(pulse),
+                    false,
                 )
-            } else if let Some((i, _)) = game
-                .snake
-                .iter()
-                .enumerate()
-                .find(|(_, p)| p.x == x && p.y == y)
-            {
+            } else if game.poison.is_some_and(|p| p.x == x && p.y == y) {
+                (
+                    glyphs.poison,
+                    Style::default()
+                        .fg(theme.poison.tui())
+                        .add_modifier(Modifier::BOLD),
+                    false,
+                )
+            } else if game.magnet.is_some_and(|p| p.x == x && p.y == y) {
+                (
+                    glyphs.magnet,
+                    Style::default()
+                        .fg(theme.special_apple.tui())
+                        .add_modifier(Modifier::BOLD),
+                    false,
+                )
+            } else if on_special {
+                (
+                    glyphs.special_apple,
+                    Style::default()
+                        .fg(theme.special_apple.tui())
+                        .add_modifier(Modifier::BOLD),
+                    false,
+                )
+            } else if let Some(&(player, i)) = segments.get(&Point { x, y }) {
+                let (head, body) = if player == 0 {
+                    (theme.snake_head.tui(), theme.snake_body.tui())
+                } else {
+                    (theme.rival_head.tui(), theme.rival_body.tui())
+                };
                 if i == 0 {
+                    // The direction moved this tick, so the arrow turns with the snake
+                    let glyph = if game.settings.directional_head {
+                        game.snakes[player].dir.arrow(glyphs.ascii)
+                    } else {
+                        glyphs.head
+                    };
                     (
-                        "■",
-                        Style::default()
-                            .fg(Color::LightGreen)
-                            .add_modifier(Modifier::BOLD),
+                        glyph,
+                        Style::default().fg(head).add_modifier(Modifier::BOLD),
+                        false,
                     )
                 } else {
-                    ("■", Style::default().fg(Color::Green))
+                    let style = Style::default().fg(if game.settings.rainbow {
+                        RAINBOW[i % RAINBOW.len()]
+                    } else {
+                        body
+                    });
+                    // Line bodies fall back to blocks where a link jumps,
+                    // e.g. through a portal
+                    let body = &game.snakes[player].body;
+                    let p = Point { x, y };
+                    let links: Option<Vec<DirectionEnum>> = [body.get(i - 1), body.get(i + 1)]
+                        .into_iter()
+                        .flatten()
+                        .map(|&q| game.link(p, q))
+                        .collect();
+                    match links.filter(|_| game.settings.line_body).and_then(|links| {
+                        let glyph = line_glyph(&links, glyphs.ascii)?;
+                        joins_right = links.contains(&DirectionEnum::Right);
+                        line_piece = true;
+                        Some(glyph)
+                    }) {
+                        Some(glyph) => (glyph, style, false),
+                        None => (glyphs.body, style, true),
+                    }
                 }
+            } else if game.walls.contains(&Point { x, y }) {
+                (glyphs.wall, Style::default().fg(theme.border.tui()), true)
+            } else if game.is_portal(Point { x, y }) {
+                (
+                    glyphs.portal,
+                    Style::default()
+                        .fg(theme.portal.tui())
+                        .add_modifier(Modifier::BOLD),
+                    false,
+                )
+            } else if game.next_apple == Some(Point { x, y }) {
+                (
+                    glyphs.apple,
+                    Style::default()
+                        .fg(theme.apple.tui())
+                        .add_modifier(Modifier::DIM),
+                    false,
+                )
+            } else if let Some(&i) = ghost.get(&Point { x, y }) {
+                (
+                    if i == 0 { glyphs.head } else { glyphs.body },
+                    Style::default()
+                        .fg(theme.border.tui())
+                        .add_modifier(Modifier::DIM),
+                    i != 0,
+                )
+            } else if let Some(&(_, life)) = game
+                .trail
+                .as_ref()
+                .and_then(|t| t.iter().rev().find(|(p, _)| p.x == x && p.y == y))
+            {
+                // Fresher trail cells are brighter (grayscale ramp)
+                (
+                    glyphs.body,
+                    Style::default().fg(Color::Indexed(235 + life * 3)),
+                    true,
+                )
             } else {
-                (" ", Style::default().bg(Color::Black))
+                (" ", Style::default().bg(cell_bg), true)
             };
-            spans.push(Span::styled(ch, style));
+            // Glyphs sit on the checker shade so the pattern stays unbroken
+            let style = if grid { style.bg(cell_bg) } else { style };
+            let text = if joins_right {
+                let line = if glyphs.ascii { "-" } else { "─" };
+                format!("{}{}", ch, line.repeat(columns - 1))
+            } else if line_piece {
+                format!("{:<columns$}", ch)
+            } else {
+                cell(ch, solid)
+            };
+            spans.push(Span::styled(text, style));
         }
         rows.push(Line::from(spans));
     }
 
     let board = Paragraph::new(rows).alignment(Alignment::Left);
     f.render_widget(board, inner);
+    if view.width < game.width || view.height < game.height {
+        draw_minimap(f, game, theme, glyphs, inner, view);
+    }
+
+    // Countdown before the snake starts moving, naming a randomly picked theme
+    if let Some(n) = game.countdown {
+        draw_banner(
+            f,
+            inner,
+            &if game.random_theme {
+                format!("{} - {} theme", n, theme.name)
+            } else {
+                n.to_string()
+            },
+            Style::default()
+                .fg(theme.title.tui())
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+
+    // Pause overlay; turns pressed meanwhile stay buffered
+    if game.show_help {
+        draw_help(f, inner, game, theme, keys);
+    } else if game.paused && !game.game_over {
+        draw_pause(f, inner, theme, keys, game.hardcore);
+    } else if game.won {
+        draw_banner(
+            f,
+            inner,
+            "YOU WIN!",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
 
     // Bottom info line with controls
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    // Up to two full movement sets, e.g. "W A S D / H J K L"
+    let move_labels = [
+        DirectionEnum::Up,
+        DirectionEnum::Left,
+        DirectionEnum::Down,
+        DirectionEnum::Right,
+    ]
+    .map(|d| keys.labels(Action::Move(d)));
+    let sets = move_labels
+        .iter()
+        .map(Vec::len)
+        .min()
+        .unwrap_or(0)
+        .clamp(1, 2);
+    let moves = (0..sets)
+        .map(|i| {
+            move_labels
+                .iter()
+                .map(|l| l.get(i).map_or("?", String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" / ");
     let mut status_text = vec![
         Span::raw("Use "),
-        Span::styled("W A S D", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(moves, bold),
         Span::raw(" to move. "),
-        Span::styled("Q", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" to quit."),
+        Span::styled(keys.label(Action::Pause), bold),
+        Span::raw(" to pause. "),
+        Span::styled(keys.label(Action::Quit), bold),
+        Span::raw(" to quit. "),
+        Span::styled(keys.label(Action::Help), bold),
+        Span::raw(" for help."),
     ];
 
+    // Golden apple despawn countdown
+    if let Some(remaining) = game.special_remaining() {
+        status_text.push(Span::raw("  "));
+        status_text.push(Span::styled(
+            format!("$ {}s", remaining.as_secs() + 1),
+            Style::default()
+                .fg(theme.special_apple.tui())
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if game.hardcore && !game.game_over {
+        status_text.push(Span::raw("  "));
+        status_text.push(Span::styled(
+            "HARDCORE — no restart",
+            Style::default()
+                .fg(theme.alert.tui())
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
     // Show restart prompt on game over
     if game.game_over {
+        let outcome = if game.playback {
+            "REPLAY FINISHED".to_string()
+        } else if game.hardcore {
+            "GAME OVER - HARDCORE — no restart".to_string()
+        } else if game.won {
+            format!("YOU WIN! - Final score: {}", game.score)
+        } else if game.time_up {
+            format!("TIME UP - Final score: {}", game.score)
+        } else if game.is_versus() {
+            match game.winner {
+                Some(p) => format!("PLAYER {} WINS", p + 1),
+                None => "DRAW".to_string(),
+            }
+        } else {
+            "GAME OVER".to_string()
+        };
         status_text.push(Span::raw("  "));
         status_text.push(Span::styled(
-            "GAME OVER - Press R to restart or Q to quit",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            format!("{} - Press {}", outcome, game_over_keys(game, keys)),
+            Style::default()
+                .fg(if game.won {
+                    Color::Green
+                } else {
+                    theme.alert.tui()
+                })
+                .add_modifier(Modifier::BOLD),
         ));
     }
 
@@ -278,189 +1331,2025 @@ fn draw_game<B: ratatui::backend::Backend>(f: &mut Frame<B>, game: &Game, area:
     f.render_widget(status, chunks[2]);
 }
 
-/// Draws the main menu screen
-fn draw_menu<B: ratatui::backend::Backend>(f: &mut Frame<B>, area: Rect) {
-    let block = Block::default().borders(Borders::ALL).title("Snake - Menu");
-    f.render_widget(block, area);
-
-    let inner = Rect {
-        x: area.x + 1,
-        y: area.y + 1,
-        width: area.width.saturating_sub(2),
-        height: area.height.saturating_sub(2),
-    };
-    let lines = vec![
-        Line::from(Span::styled(
-            "Welcome to Snake (Terminal Edition)",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Line::from(Span::raw(" ")),
-        Line::from(Span::raw("Press Enter to start")),
-        Line::from(Span::raw("Press Q to quit")),
-    ];
-    let p = Paragraph::new(lines).alignment(Alignment::Center);
-    f.render_widget(p, inner);
+/// What the keys do once a game is over; hardcore runs and replays can only
+/// quit
+fn game_over_keys(game: &Game, keys: &KeyMap) -> String {
+    if game.hardcore || game.playback {
+        format!("{} to quit", keys.label(Action::Quit))
+    } else {
+        format!(
+            "{} to restart, {} for the menu or {} to quit",
+            keys.label(Action::Restart),
+            keys.label(Action::Menu),
+            keys.label(Action::Quit)
+        )
+    }
 }
 
-/// Entry point
-fn main() -> Result<(), io::Error> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
-
-    let res = run_app(&mut terminal);
-
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    if let Err(err) = res {
-        eprintln!("Error: {:?}", err);
+/// Returns a rect of the given size centered inside `area`
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
-    Ok(())
+}
+
+/// Draws a small boxed message centered over the board
+fn draw_banner<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    text: &str,
+    style: Style,
+) {
+    let rect = centered_rect(text.chars().count() as u16 + 6, 3, area);
+    f.render_widget(Clear, rect);
+    let banner = Paragraph::new(Line::from(Span::styled(text.to_string(), style)))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(style));
+    f.render_widget(banner, rect);
+}
+
+/// Help overlay: the mode being played, what it takes to win, and every
+/// key binding as currently mapped
+fn draw_help<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    game: &Game,
+    theme: &Theme,
+    keys: &KeyMap,
+) {
+    let title = Style::default()
+        .fg(theme.title.tui())
+        .add_modifier(Modifier::BOLD);
+    let mut mode = vec![game.settings.difficulty.name(), game.wrap.label()];
+    if game.is_versus() {
+        mode.push("versus");
+    }
+    if game.settings.zen {
+        mode.push("zen");
+    }
+    if game.hardcore {
+        mode.push("hardcore");
+    }
+    if game.daily.is_some() {
+        mode.push("daily challenge");
+    }
+    let goal = if let Some(target) = game.target {
+        format!("Reach {} points", target)
+    } else if game.is_versus() {
+        "Outlast the other snake".to_string()
+    } else if game.settings.zen {
+        "Relax; nothing can crash".to_string()
+    } else {
+        "Eat apples; avoid walls and your tail".to_string()
+    };
+    let mut lines = vec![
+        Line::from(Span::styled("HELP", title)),
+        Line::from(" "),
+        Line::from(format!("Mode: {}", mode.join(", "))),
+        Line::from(format!("Goal: {}", goal)),
+        Line::from(" "),
+    ];
+    for action in Action::ALL {
+        let labels = keys.labels(action);
+        if labels.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(format!(
+            "{:>9}  {:<22}",
+            labels.join("/"),
+            action.describe()
+        )));
+    }
+    if game.is_versus() {
+        lines.push(Line::from(format!("{:>9}  {:<22}", "Arrows", "player two")));
+    }
+    lines.push(Line::from(" "));
+    lines.push(Line::from("Press any key to close"));
+
+    let rect = centered_rect(40, lines.len() as u16 + 2, area);
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border.tui())),
+        ),
+        rect,
+    );
+}
+
+/// Pause overlay listing the keys that work while paused
+fn draw_pause<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    theme: &Theme,
+    keys: &KeyMap,
+    hardcore: bool,
+) {
+    let style = Style::default()
+        .fg(theme.title.tui())
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled("PAUSED", style))];
+    for (action, what) in [
+        (Action::Pause, "resume"),
+        (Action::Restart, "restart"),
+        (Action::Menu, "main menu"),
+        (Action::Quit, "quit"),
+    ] {
+        if hardcore && matches!(action, Action::Restart | Action::Menu) {
+            continue;
+        }
+        lines.push(Line::from(format!(
+            "{:>5}  {:<9}",
+            keys.label(action),
+            what
+        )));
+    }
+    let rect = centered_rect(24, lines.len() as u16 + 2, area);
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL).border_style(style)),
+        rect,
+    );
+}
+
+/// Formats a run time as `m:ss.mmm`
+fn format_run_time(time: Duration) -> String {
+    let ms = time.as_millis();
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+/// Formats a split difference in milliseconds as `+s.mmm` or `-s.mmm`
+fn format_delta(delta: i64) -> String {
+    let sign = if delta <= 0 { '-' } else { '+' };
+    let ms = delta.unsigned_abs();
+    format!("{}{}.{:03}", sign, ms / 1000, ms % 1000)
+}
+
+/// Lists a finished speedrun's splits next to the personal best's
+fn draw_splits<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    game: &Game,
+    new_best: bool,
+    theme: &Theme,
+    keys: &KeyMap,
+) {
+    let Some(goal) = game.speedrun_goal else {
+        return;
+    };
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("Goal {} in {}", goal, format_run_time(game.run_time())),
+        bold,
+    ))];
+    if new_best {
+        lines.push(Line::from(Span::styled(
+            "NEW PERSONAL BEST!",
+            bold.fg(theme.alert.tui()),
+        )));
+    }
+    lines.push(Line::from(" "));
+    for (i, (mark, time)) in split_marks(goal).into_iter().zip(&game.splits).enumerate() {
+        let mut spans = vec![Span::raw(format!(
+            "{:>4}  {}",
+            mark,
+            format_run_time(*time)
+        ))];
+        if let Some(best) = game.best_splits.get(i) {
+            let delta = time.as_millis() as i64 - best.as_millis() as i64;
+            spans.push(Span::styled(
+                format!("  {:>7}", format_delta(delta)),
+                Style::default().fg(if delta <= 0 {
+                    Color::Green
+                } else {
+                    theme.alert.tui()
+                }),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(" "));
+    lines.push(Line::from(game_over_keys(game, keys)));
+
+    let rect = centered_rect(42, lines.len() as u16 + 2, area);
+    f.render_widget(Clear, rect);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border.tui()))
+        .title(" Splits ");
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(block),
+        rect,
+    );
+}
+
+/// Draws the end-of-run summary, including the initials prompt if any
+fn draw_stats<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    game: &Game,
+    play_time: Duration,
+    new_record: bool,
+    initials: Option<&str>,
+    keys: &KeyMap,
+) {
+    let theme = game.theme();
+    let alert = Style::default()
+        .fg(theme.alert.tui())
+        .add_modifier(Modifier::BOLD);
+    let secs = play_time.as_secs();
+    let mut lines = vec![
+        if game.won {
+            Line::from(Span::styled("YOU WIN!", alert.fg(Color::Green)))
+        } else {
+            Line::from(Span::styled(
+                if game.time_up { "TIME UP" } else { "GAME OVER" },
+                alert,
+            ))
+        },
+        Line::from(Span::raw(" ")),
+        Line::from(Span::raw(format!("Score:  {}", game.score))),
+        Line::from(Span::raw(format!("Level:  {}", game.level))),
+        Line::from(Span::raw(format!("Length: {}", game.snakes[0].body.len()))),
+        Line::from(Span::raw(format!("Apples: {}", game.apples_eaten))),
+        Line::from(Span::raw(format!("Time:   {}:{:02}", secs / 60, secs % 60))),
+    ];
+    if game.carry_bonus() > 0 {
+        lines.push(Line::from(Span::raw(format!(
+            "Carried over: {}",
+            game.carry_bonus()
+        ))));
+    }
+    if new_record {
+        lines.push(Line::from(Span::raw(" ")));
+        lines.push(Line::from(Span::styled(
+            if game.daily.is_some() {
+                "NEW DAILY BEST!"
+            } else {
+                "NEW RECORD!"
+            },
+            alert,
+        )));
+    }
+    lines.push(Line::from(Span::raw(" ")));
+    match initials {
+        Some(name) => {
+            lines.push(Line::from(Span::styled(
+                format!("Initials: {:_<3}", name),
+                alert,
+            )));
+            lines.push(Line::from(Span::raw("Enter to save, Esc to skip")));
+        }
+        None => lines.push(Line::from(Span::raw(game_over_keys(game, keys)))),
+    }
+
+    let rect = centered_rect(32, lines.len() as u16 + 2, area);
+    f.render_widget(Clear, rect);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border.tui()))
+        .title(" Summary ");
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(block),
+        rect,
+    );
+}
+
+/// Draws tick duration, FPS, and snake length in the top-right corner
+fn draw_debug<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    stats: &FrameStats,
+    tick: Duration,
+    base_tick_ms: u64,
+    length: usize,
+) {
+    let text = format!(
+        " tick {}ms (base {}ms) | {:.0} fps | len {} ",
+        tick.as_millis(),
+        base_tick_ms,
+        stats.fps(),
+        length
+    );
+    let width = (text.chars().count() as u16).min(area.width);
+    let rect = Rect {
+        x: area.x + area.width - width,
+        y: area.y,
+        width,
+        height: 1.min(area.height),
+    };
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().add_modifier(Modifier::REVERSED)),
+        rect,
+    );
+}
+
+/// Area inside the menu's border, where the demo game plays
+fn menu_inner(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    }
+}
+
+/// Draws the demo game dimmed, in plain ASCII so it never competes with
+/// the menu text
+fn draw_demo<B: ratatui::backend::Backend>(f: &mut Frame<B>, demo: &Game, area: Rect) {
+    let glyphs = Glyphs::new(&THEMES[0], true);
+    let rows: Vec<Line> = (0..demo.height)
+        .map(|y| {
+            let row: String = (0..demo.width)
+                .map(|x| {
+                    let p = Point { x, y };
+                    if demo.snakes[0].head() == p {
+                        glyphs.head
+                    } else if demo.occupied.contains(&p) {
+                        glyphs.body
+                    } else if demo.is_apple(p) {
+                        glyphs.apple
+                    } else {
+                        " "
+                    }
+                })
+                .collect();
+            Line::from(row)
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(rows).style(Style::default().fg(Color::DarkGray)),
+        area,
+    );
+}
+
+/// Draws the main menu screen over the demo game, if one is running
+fn draw_menu<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    settings: &Settings,
+    demo: Option<&Game>,
+) {
+    let block = Block::default().borders(Borders::ALL).title("Snake - Menu");
+    f.render_widget(block, area);
+
+    let inner = menu_inner(area);
+    if let Some(demo) = demo {
+        draw_demo(f, demo, inner);
+    }
+    let lines = vec![
+        Line::from(Span::styled(
+            "Welcome to Snake (Terminal Edition)",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::raw(" ")),
+        Line::from(Span::raw(format!(
+            "Difficulty: {} (D to change)",
+            settings.difficulty.name()
+        ))),
+        Line::from(Span::raw(format!(
+            "Walls: {} (Tab to change)",
+            settings.wrap.label()
+        ))),
+        Line::from(Span::raw(format!(
+            "Starting speed: {} (scroll to change)",
+            settings.value(SettingsItem::StartSpeed)
+        ))),
+        Line::from(Span::raw(format!(
+            "Poison food: {} (P to toggle)",
+            if settings.poison { "on" } else { "off" }
+        ))),
+        Line::from(Span::raw(format!(
+            "Fog of war: {} (F to toggle)",
+            settings.value(SettingsItem::Fog)
+        ))),
+        Line::from(Span::raw(format!(
+            "Zen Mode: {} (Z to toggle)",
+            if settings.zen { "on" } else { "off" }
+        ))),
+        Line::from(Span::raw(" ")),
+        Line::from(Span::raw("Press Enter to start")),
+        Line::from(Span::raw("Press C for the campaign")),
+        Line::from(Span::raw("Press S for settings")),
+        Line::from(Span::raw("Press L for the leaderboard")),
+        Line::from(Span::raw("Press Q to quit")),
+    ];
+    let p = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(p, inner);
+}
+
+/// Shown instead of the board until the terminal is big enough to play
+fn draw_too_small<B: ratatui::backend::Backend>(f: &mut Frame<B>, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            format!(
+                "Please enlarge your terminal (min {}x{})",
+                MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::raw(format!(
+            "Currently {}x{} - Q to quit",
+            area.width, area.height
+        ))),
+    ];
+    let p = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(p, area);
+}
+
+/// Draws the settings screen with the selected row highlighted
+fn draw_settings<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    settings: &Settings,
+    selected: usize,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Snake - Settings");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut lines = vec![Line::from(Span::raw(" "))];
+    for (i, item) in SettingsItem::ALL.iter().enumerate() {
+        let style = if i == selected {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let marker = if i == selected { "> " } else { "  " };
+        lines.push(Line::from(Span::styled(
+            format!("{}{}: {}", marker, item.label(), settings.value(*item)),
+            style,
+        )));
+    }
+    lines.push(Line::from(Span::raw(" ")));
+    lines.push(Line::from(Span::raw(
+        "Up/Down to select, Left/Right or Enter to change, Esc to go back",
+    )));
+
+    let p = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(p, inner);
+}
+
+/// Draws the leaderboard screen with ranks, initials and scores
+fn draw_leaderboard<B: ratatui::backend::Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    board: &Leaderboard,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Snake - Leaderboard");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut lines = vec![Line::from(Span::raw(" "))];
+    if board.entries.is_empty() {
+        lines.push(Line::from(Span::raw("No scores yet")));
+    }
+    for (i, (name, score)) in board.entries.iter().enumerate() {
+        let style = if i == 0 {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{:>2}. {}  {:>6}", i + 1, name, score),
+            style,
+        )));
+    }
+    lines.push(Line::from(Span::raw(" ")));
+    lines.push(Line::from(Span::raw("Esc to go back")));
+
+    let p = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(p, inner);
+}
+
+/// Command-line options
+struct Args {
+    settings: Settings,
+    replay: Option<PathBuf>,
+    level: Option<PathBuf>,
+    seed: Option<u64>,
+    sound: bool,
+    pause_on_blur: bool,
+    time_attack: Option<Duration>,
+    speedrun: Option<u32>,
+    goal: Option<u32>,
+    hardcore: bool,
+    /// Date of the daily challenge being played
+    daily: Option<String>,
+    title: bool,
+    debug: bool,
+    trail: bool,
+    dump: Option<PathBuf>,
+    record_frames: Option<PathBuf>,
+    no_menu: bool,
+    text_mode: bool,
+    width: Option<u16>,
+    height: Option<u16>,
+}
+
+impl Args {
+    /// Parses flags such as `--growth 3` from the given arguments
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            // Flags override whatever was saved last session
+            settings: settings_path()
+                .map(|path| Settings::load_saved(&path))
+                .unwrap_or_default(),
+            replay: None,
+            level: None,
+            seed: None,
+            sound: false,
+            pause_on_blur: false,
+            time_attack: None,
+            speedrun: None,
+            goal: None,
+            hardcore: false,
+            daily: None,
+            title: false,
+            debug: false,
+            trail: false,
+            dump: None,
+            record_frames: None,
+            no_menu: false,
+            text_mode: false,
+            width: None,
+            height: None,
+        };
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--growth" => parsed.settings.growth_per_apple = parse_value(&flag, args.next())?,
+                "--lives" => parsed.settings.lives = parse_value(&flag, args.next())?,
+                "--max-length" => {
+                    let cap: usize = parse_value(&flag, args.next())?;
+                    if cap == 0 {
+                        return Err("--max-length must be at least 1".to_string());
+                    }
+                    parsed.settings.max_length = Some(cap);
+                }
+                "--apples" => {
+                    let count: usize = parse_value(&flag, args.next())?;
+                    if count == 0 {
+                        return Err("--apples must be at least 1".to_string());
+                    }
+                    parsed.settings.apple_count = count;
+                }
+                "--length" => parsed.settings.start_length = parse_value(&flag, args.next())?,
+                "--sound" => parsed.sound = true,
+                "--pause-on-blur" => parsed.pause_on_blur = true,
+                "--debug" => parsed.debug = true,
+                "--hardcore" => parsed.hardcore = true,
+                "--title" => parsed.title = true,
+                "--daily" => parsed.daily = Some(utc_date()),
+                "--trail" => parsed.trail = true,
+                "--time-attack" => {
+                    let secs: u64 = parse_value(&flag, args.next())?;
+                    if secs == 0 {
+                        return Err("--time-attack must be at least 1 second".to_string());
+                    }
+                    parsed.time_attack = Some(Duration::from_secs(secs));
+                }
+                "--speedrun" => {
+                    let goal: u32 = parse_value(&flag, args.next())?;
+                    if goal == 0 {
+                        return Err("--speedrun must be at least 1 point".to_string());
+                    }
+                    parsed.speedrun = Some(goal);
+                }
+                "--goal" => {
+                    let goal: u32 = parse_value(&flag, args.next())?;
+                    if goal == 0 {
+                        return Err("--goal must be at least 1 point".to_string());
+                    }
+                    parsed.goal = Some(goal);
+                }
+                "--seed" => parsed.seed = Some(parse_value(&flag, args.next())?),
+                "--replay" => parsed.replay = Some(parse_value(&flag, args.next())?),
+                "--level" => parsed.level = Some(parse_value(&flag, args.next())?),
+                "--dump" => parsed.dump = Some(parse_value(&flag, args.next())?),
+                "--record-frames" => parsed.record_frames = Some(parse_value(&flag, args.next())?),
+                "--no-menu" => parsed.no_menu = true,
+                "--text-mode" => parsed.text_mode = true,
+                "--width" => parsed.width = Some(parse_value(&flag, args.next())?),
+                "--height" => parsed.height = Some(parse_value(&flag, args.next())?),
+                "--colorblind" => parsed.settings.theme = HIGH_CONTRAST_THEME,
+                "--grid" => parsed.settings.grid = true,
+                "--ascii" => parsed.settings.ascii = true,
+                "--square-cells" => parsed.settings.square_cells = true,
+                "--cell-width" => {
+                    let width: u16 = parse_value(&flag, args.next())?;
+                    if !(1..=2).contains(&width) {
+                        return Err("--cell-width must be 1 or 2".to_string());
+                    }
+                    parsed.settings.cell_width = width;
+                }
+                "--ruler" => parsed.settings.ruler = true,
+                "--theme" => {
+                    let name: String = parse_value(&flag, args.next())?;
+                    parsed
+                        .settings
+                        .set_kv("theme", &name)
+                        .map_err(|_| format!("unknown theme: {}", name))?;
+                }
+                other => return Err(format!("unknown argument: {}", other)),
+            }
+        }
+        // Everyone gets the same game on the same day
+        if let Some(date) = &parsed.daily {
+            if parsed.seed.is_some()
+                || parsed.level.is_some()
+                || parsed.width.is_some()
+                || parsed.height.is_some()
+                || parsed.time_attack.is_some()
+                || parsed.speedrun.is_some()
+                || parsed.goal.is_some()
+            {
+                return Err("--daily plays a fixed game; it can't be combined with \
+                     --seed, --level, --width, --height, --time-attack, --speedrun or --goal"
+                    .to_string());
+            }
+            parsed.seed = Some(daily_seed(date));
+        }
+        if parsed.settings.lives == 0 {
+            return Err("--lives must be at least 1".to_string());
+        }
+        if parsed.settings.growth_per_apple == 0 {
+            return Err("--growth must be at least 1".to_string());
+        }
+        // Longer snakes are clamped to the board when a game starts
+        if parsed.settings.start_length == 0 {
+            return Err("--length must be at least 1".to_string());
+        }
+        if parsed.width.is_some_and(|w| w < 10) || parsed.height.is_some_and(|h| h < 5) {
+            return Err("the board must be at least 10x5".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+/// Parses the value that follows a flag
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} expects a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+/// Entry point
+fn main() -> Result<(), io::Error> {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let replay = match args.replay.as_deref().map(Replay::load).transpose() {
+        // Glyphs depend on the viewer's terminal, not the recording
+        Ok(replay) => replay.map(|mut replay| {
+            replay.settings = replay.settings.with_glyphs_of(&args.settings);
+            replay
+        }),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let level = match args.level.as_deref().map(Level::load).transpose() {
+        Ok(level) => level,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    // A broken bindings file falls back to the default keys
+    let keys = match keys_path() {
+        Some(path) => match KeyMap::load(&path) {
+            Ok((keys, warnings)) => {
+                for warning in warnings {
+                    eprintln!("Warning: {}: {}", path.display(), warning);
+                }
+                keys
+            }
+            Err(err) => {
+                eprintln!("Warning: ignoring {}: {}", path.display(), err);
+                KeyMap::default()
+            }
+        },
+        None => KeyMap::default(),
+    };
+
+    // Frames are appended so several sessions can share one file
+    let frames = match &args.record_frames {
+        Some(path) => match fs::OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("Error: {}: {}", path.display(), err);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    // Warned about now, while stderr is still on screen
+    if let Ok((columns, rows)) = terminal::size() {
+        for warning in size_warnings(&args, columns, rows) {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+
+    let guard = TerminalGuard::enter(args.pause_on_blur, args.title)?;
+    let backend = FrameRecorder::new(CrosstermBackend::new(io::stdout()), frames);
+    let mut terminal = Terminal::new(backend)?;
+    terminal.clear()?;
+
+    let res = match &replay {
+        Some(replay) => run_replay(&mut terminal, replay).map(|_| None),
+        None => run_app(&mut terminal, &args, &keys, level.as_ref()),
+    };
+
+    // Back on the normal screen before the seed or error is printed
+    drop(guard);
+
+    match res {
+        // Report the entropy-derived seed so a good run can be replayed
+        Ok(Some(seed)) if args.seed.is_none() => {
+            println!(
+                "Last game seed: {} (play it again with --seed {})",
+                seed, seed
+            )
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("Error: {:?}", err),
+    }
+    Ok(())
+}
+
+/// Notes on `--width` and `--height` values that fall back to the size the
+/// terminal fits, or that are larger than it and will scroll
+fn size_warnings(args: &Args, columns: u16, rows: u16) -> Vec<String> {
+    let fitted = Game::board_size(columns, rows, &args.settings);
+    let sides = [
+        ("--width", args.width, fitted.0, MIN_BOARD_WIDTH),
+        ("--height", args.height, fitted.1, MIN_BOARD_HEIGHT),
+    ];
+    sides
+        .into_iter()
+        .filter_map(|(flag, requested, fits, min)| match requested? {
+            n if n < min => Some(format!(
+                "{} {} is below the minimum of {}; using {} to fit the terminal",
+                flag, n, min, fits
+            )),
+            n if n > fits => Some(format!(
+                "{} {} is more than the terminal shows ({}); the view will scroll",
+                flag, n, fits
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Set while the terminal is in raw mode on the alternate screen
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Raw mode, the alternate screen and the other terminal changes `main`
+/// makes, undone when the guard is dropped
+///
+/// A panic hook undoes them too, before the panic message prints, so a
+/// crash leaves a usable shell and a readable message behind.
+struct TerminalGuard {
+    focus_events: bool,
+    title: bool,
+}
+
+impl TerminalGuard {
+    fn enter(focus_events: bool, title: bool) -> io::Result<Self> {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal(focus_events, title);
+            previous(info);
+        }));
+
+        enable_raw_mode()?;
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        // Built before the rest so a failure below still restores raw mode
+        let guard = Self {
+            focus_events,
+            title,
+        };
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        // Focus events are opt-in since not every terminal reports them
+        if focus_events {
+            execute!(stdout, EnableFocusChange)?;
+        }
+        // Save the current title on the terminal's title stack (xterm's
+        // CSI 22 t), since there's no portable way to read it back
+        if title {
+            write!(stdout, "\x1b[22;0t")?;
+            stdout.flush()?;
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(self.focus_events, self.title);
+    }
+}
+
+/// Undoes [`TerminalGuard::enter`]; only the first call does anything, so
+/// the panic hook and the guard can both run. Errors are ignored since
+/// there's nothing left to report them to
+fn restore_terminal(focus_events: bool, title: bool) {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let mut stdout = io::stdout();
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
+    if focus_events {
+        let _ = execute!(stdout, DisableFocusChange);
+    }
+    // Blank the title first so terminals without a title stack don't keep
+    // showing the last score
+    if title {
+        let _ = execute!(stdout, SetTitle(""));
+        let _ = write!(stdout, "\x1b[23;0t");
+    }
+    let _ = execute!(stdout, Show);
+}
+
+/// Backend that passes everything through and, for `--record-frames`,
+/// keeps a copy of the screen to append each changed frame to a file as
+/// ANSI text
+///
+/// Ratatui only hands the backend cells that differ from the last frame, so
+/// a draw that changed nothing writes nothing.
+struct FrameRecorder<B, W = BufWriter<fs::File>> {
+    inner: B,
+    out: Option<W>,
+    screen: Buffer,
+    changed: bool,
+}
+
+impl<B: Backend, W: Write> FrameRecorder<B, W> {
+    fn new(inner: B, out: Option<W>) -> Self {
+        Self {
+            inner,
+            out,
+            screen: Buffer::empty(Rect::default()),
+            changed: false,
+        }
+    }
+}
+
+impl<B: Backend, W: Write> Backend for FrameRecorder<B, W> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        if self.out.is_none() {
+            return self.inner.draw(content);
+        }
+        // A resize makes ratatui redraw every cell, so nothing is lost
+        let area = self.inner.size()?;
+        if self.screen.area != area {
+            self.screen = Buffer::empty(area);
+        }
+        let cells: Vec<_> = content.collect();
+        for &(x, y, cell) in &cells {
+            if x < area.width && y < area.height {
+                *self.screen.get_mut(x, y) = cell.clone();
+                self.changed = true;
+            }
+        }
+        self.inner.draw(cells.into_iter())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        self.inner.get_cursor()
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.screen.reset();
+        self.inner.clear()
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        self.inner.size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(out) = self.out.as_mut()
+            && self.changed
+        {
+            out.write_all(frame_ansi(&self.screen).as_bytes())?;
+            out.flush()?;
+            self.changed = false;
+        }
+        Ok(())
+    }
+}
+
+/// One frame as ANSI text: clear the screen, then every row, changing the
+/// style only where it differs from the cell before
+///
+/// `cat`-ing a file of these plays the frames back in a terminal.
+fn frame_ansi(screen: &Buffer) -> String {
+    let mut out = String::from("\x1b[H\x1b[2J");
+    for y in 0..screen.area.height {
+        let mut style = None;
+        for x in 0..screen.area.width {
+            let cell = screen.get(x, y);
+            let now = (cell.fg, cell.bg, cell.modifier);
+            if style != Some(now) {
+                out += &sgr(cell.fg, cell.bg, cell.modifier);
+                style = Some(now);
+            }
+            out += &cell.symbol;
+        }
+        out += "\x1b[0m\r\n";
+    }
+    out
+}
+
+/// Escape sequence selecting the given colours and modifiers from scratch
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    const MODIFIERS: [(Modifier, u8); 9] = [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ];
+    let mut codes = vec!["0".to_string()];
+    for (flag, code) in MODIFIERS {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.push(color_code(fg, false));
+    codes.push(color_code(bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// SGR parameter for a foreground or background colour
+fn color_code(color: Color, background: bool) -> String {
+    let base = if background { 10 } else { 0 };
+    let code = match color {
+        Color::Reset => 39,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Rgb(r, g, b) => return format!("{};2;{};{};{}", 38 + base, r, g, b),
+        Color::Indexed(i) => return format!("{};5;{}", 38 + base, i),
+    };
+    (code + base).to_string()
+}
+
+/// Maps the library's theme colors onto ratatui's
+trait Tui {
+    fn tui(self) -> Color;
+}
+
+impl Tui for snake_game::Color {
+    fn tui(self) -> Color {
+        use snake_game::Color as C;
+        match self {
+            C::Black => Color::Black,
+            C::Red => Color::Red,
+            C::Green => Color::Green,
+            C::Yellow => Color::Yellow,
+            C::Blue => Color::Blue,
+            C::Magenta => Color::Magenta,
+            C::Cyan => Color::Cyan,
+            C::Gray => Color::Gray,
+            C::DarkGray => Color::DarkGray,
+            C::LightRed => Color::LightRed,
+            C::LightGreen => Color::LightGreen,
+            C::LightYellow => Color::LightYellow,
+            C::LightBlue => Color::LightBlue,
+            C::LightMagenta => Color::LightMagenta,
+            C::LightCyan => Color::LightCyan,
+            C::White => Color::White,
+            C::Rgb(r, g, b) => Color::Rgb(r, g, b),
+            C::Indexed(i) => Color::Indexed(i),
+        }
+    }
+}
+
+/// True when the terminal advertises 24-bit color, checked once
+fn truecolor() -> bool {
+    static TRUECOLOR: OnceLock<bool> = OnceLock::new();
+    *TRUECOLOR
+        .get_or_init(|| std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit"))
+}
+
+/// Puts the score and level in the terminal title for `--title`, writing
+/// only when they differ from what's already shown
+fn update_title(shown: &mut Option<(u32, u32)>, game: &Game) -> io::Result<()> {
+    let now = (game.score, game.level);
+    if *shown == Some(now) {
+        return Ok(());
+    }
+    *shown = Some(now);
+    execute!(
+        io::stdout(),
+        SetTitle(format!("Snake - Score {} - Level {}", now.0, now.1))
+    )
 }
 
 /// Game loop: handles menu, game, and restart logic
-fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
-    let mut show_menu = true;
+///
+/// Every game outside the campaign is played on `custom_level` when one was
+/// loaded with `--level`. Returns the seed of the last game played, if any.
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    args: &Args,
+    keys: &KeyMap,
+    custom_level: Option<&Level>,
+) -> io::Result<Option<u64>> {
+    let mut screen = AppScreen::Menu;
+    // Score and level last written to the terminal title
+    let mut shown_title: Option<(u32, u32)> = None;
+    let mut settings = args.settings.clone();
+    let mut selected = 0;
     let mut game_opt: Option<Game> = None;
+    let mut leaderboard = Leaderboard::default();
 
-    loop {
-        // Draw either the menu or the game
+    // Every game uses the `--seed` value when one was given
+    let sound = args.sound.then(spawn_sound_player);
+    let new_game = |size: Rect, settings: &Settings| {
+        // Settings changed in the menu can't alter a daily challenge
+        let daily;
+        let settings = match &args.daily {
+            Some(_) => {
+                daily = settings.daily();
+                &daily
+            }
+            None => settings,
+        };
+        // --width and --height may ask for more than fits; the view scrolls
+        let seed = args.seed.unwrap_or_else(rand::random);
+        let mut game = match custom_level {
+            Some(level) => Game::from_level(level, settings, seed),
+            None => Game::with_size(
+                args.width,
+                args.height,
+                size.width,
+                size.height,
+                settings,
+                seed,
+            ),
+        };
+        game.time_limit = args.time_attack;
+        // Race the best run for this clock, if it was played on the same board
+        if let Some(limit) = args.time_attack
+            && custom_level.is_none()
+            && let Some(best) = best_run_path(limit).and_then(|path| Replay::load(&path).ok())
+            && (best.width, best.height) == (game.width, game.height)
+        {
+            game.ghost = Some(Box::new(Ghost::new(best)));
+        }
+        game.hardcore = args.hardcore;
+        game.high_score = load_high_score();
+        game.daily = args.daily.clone();
+        // Combos are timed by the clock, so the daily leaves them out to
+        // stay the same game for everyone
+        game.combos_enabled = args.daily.is_none();
+        game.trail = args.trail.then(VecDeque::new);
+        if args.debug {
+            game.keep_history();
+        }
+        game.target_score = args.goal;
+        if let Some(goal) = args.speedrun {
+            let best = splits_path()
+                .map(|path| BestSplits::load(&path))
+                .unwrap_or_default();
+            game.start_speedrun(goal, best.get(goal).unwrap_or_default().to_vec());
+        }
+        game
+    };
+
+    // Campaign levels are played in order, each starting at the length the
+    // previous one ended with
+    let levels = Level::campaign();
+    let mut campaign_index: Option<usize> = None;
+    let mut campaign_length = settings.start_length;
+    let level_game = |index: usize, length: u16, settings: &Settings| {
+        let settings = Settings {
+            start_length: length,
+            ..settings.clone()
+        };
+        let seed = args.seed.unwrap_or_else(rand::random);
+        let mut game = Game::from_level(&levels[index], &settings, seed);
+        game.combos_enabled = true;
+        game.high_score = load_high_score();
+        game.hardcore = args.hardcore;
+        game.trail = args.trail.then(VecDeque::new);
+        if args.debug {
+            game.keep_history();
+        }
+        game
+    };
+
+    // --no-menu goes straight into a game, so the first frame is the board
+    if args.no_menu {
+        game_opt = Some(new_game(terminal.get_frame().size(), &settings));
+        screen = AppScreen::Game;
+    }
+
+    // Set while a game is held back by a too-small terminal
+    let mut waiting_for_room = false;
+    // Self-playing game behind the menu; only stepped while the menu shows
+    let mut demo: Option<Game> = None;
+    let mut demo_tick = Instant::now();
+
+    'app: loop {
+        // Hold a new game back until the terminal fits a playable board
+        if screen == AppScreen::Game {
+            let size = terminal.size()?;
+            if size.width < MIN_TERMINAL_WIDTH || size.height < MIN_TERMINAL_HEIGHT {
+                waiting_for_room = true;
+                terminal.draw(|f| draw_too_small(f, f.size()))?;
+                if let Some(Event::Key(KeyEvent {
+                    code: KeyCode::Char('q') | KeyCode::Char('Q'),
+                    ..
+                })) = poll_event(Duration::from_millis(200))?
+                {
+                    return Ok(None);
+                }
+                continue;
+            }
+            // The board was sized for the old, cramped terminal
+            if waiting_for_room {
+                waiting_for_room = false;
+                if campaign_index.is_none() {
+                    game_opt = Some(new_game(size, &settings));
+                }
+            }
+        }
+
+        // Keep the demo sized to the menu, starting over when it crashes
+        if screen == AppScreen::Menu {
+            let inner = menu_inner(terminal.size()?);
+            let fits = demo
+                .as_ref()
+                .is_some_and(|d| d.width == inner.width && d.height == inner.height);
+            if inner.width < 10 || inner.height < 5 {
+                demo = None;
+            } else if !fits || demo.as_ref().is_some_and(|d| d.game_over) {
+                let mut d = Game::from_board(
+                    inner.width,
+                    inner.height,
+                    &Settings::default(),
+                    rand::random(),
+                );
+                d.countdown = None;
+                d.autopilot = true;
+                demo = Some(d);
+            }
+            if let Some(d) = demo.as_mut()
+                && demo_tick.elapsed() >= DEMO_TICK
+            {
+                d.step();
+                d.events.clear();
+                demo_tick = Instant::now();
+            }
+        }
+
+        // Draw the current screen
         terminal.draw(|f| {
             let size = f.size();
-            if show_menu {
-                draw_menu(f, size);
-            } else if let Some(g) = &game_opt {
-                draw_game(f, g, size);
+            match screen {
+                AppScreen::Menu => draw_menu(f, size, &settings, demo.as_ref()),
+                AppScreen::Settings => draw_settings(f, size, &settings, selected),
+                AppScreen::Leaderboard => draw_leaderboard(f, size, &leaderboard),
+                AppScreen::Game => {
+                    if let Some(g) = &game_opt {
+                        draw_game(
+                            f,
+                            g,
+                            g.theme(),
+                            &Glyphs::new(g.theme(), settings.ascii),
+                            keys,
+                            size,
+                        );
+                    }
+                }
             }
         })?;
 
         // Menu input handling
-        if show_menu {
-            if event::poll(Duration::from_millis(200))? {
-                if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                    match code {
-                        KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(()),
-                        KeyCode::Enter => {
-                            let size = terminal.get_frame().size();
-                            game_opt = Some(Game::new(size));
-                            show_menu = false;
+        if screen == AppScreen::Menu {
+            let code = match poll_event(DEMO_TICK)? {
+                Some(Event::Key(KeyEvent { code, .. })) => Some(code),
+                // The wheel nudges the starting speed: up is faster
+                Some(Event::Mouse(MouseEvent { kind, .. })) => {
+                    match kind {
+                        MouseEventKind::ScrollUp => settings.adjust(SettingsItem::StartSpeed, true),
+                        MouseEventKind::ScrollDown => {
+                            settings.adjust(SettingsItem::StartSpeed, false)
                         }
                         _ => {}
                     }
+                    None
+                }
+                _ => None,
+            };
+            if let Some(code) = code {
+                match code {
+                    KeyCode::Char('q') | KeyCode::Char('Q') => {
+                        return Ok(game_opt.map(|g| g.seed));
+                    }
+                    KeyCode::Tab => settings.wrap = settings.wrap.cycle(true),
+                    KeyCode::Char('d') | KeyCode::Char('D') => {
+                        settings.set_difficulty(settings.difficulty.cycle(true))
+                    }
+                    KeyCode::Char('p') | KeyCode::Char('P') => settings.poison = !settings.poison,
+                    KeyCode::Char('z') | KeyCode::Char('Z') => settings.zen = !settings.zen,
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        settings.fog_radius = match settings.fog_radius {
+                            Some(_) => None,
+                            None => Some(DEFAULT_FOG_RADIUS),
+                        }
+                    }
+                    KeyCode::Char('s') | KeyCode::Char('S') => {
+                        selected = 0;
+                        screen = AppScreen::Settings;
+                    }
+                    KeyCode::Char('l') | KeyCode::Char('L') => {
+                        leaderboard = leaderboard_path()
+                            .map(|path| Leaderboard::load(&path))
+                            .unwrap_or_default();
+                        screen = AppScreen::Leaderboard;
+                    }
+                    KeyCode::Enter => {
+                        let size = terminal.get_frame().size();
+                        game_opt = Some(new_game(size, &settings));
+                        campaign_index = None;
+                        screen = AppScreen::Game;
+                    }
+                    KeyCode::Char('c') | KeyCode::Char('C') => {
+                        campaign_length = settings.start_length;
+                        game_opt = Some(level_game(0, campaign_length, &settings));
+                        campaign_index = Some(0);
+                        screen = AppScreen::Game;
+                    }
+                    _ => {}
                 }
             }
             continue;
         }
 
+        // Settings input handling
+        if screen == AppScreen::Settings {
+            if let Some(Event::Key(KeyEvent { code, .. })) = poll_event(Duration::from_millis(200))?
+            {
+                let item = SettingsItem::ALL[selected];
+                match code {
+                    KeyCode::Up => {
+                        selected =
+                            (selected + SettingsItem::ALL.len() - 1) % SettingsItem::ALL.len()
+                    }
+                    KeyCode::Down => selected = (selected + 1) % SettingsItem::ALL.len(),
+                    KeyCode::Right | KeyCode::Enter => settings.adjust(item, true),
+                    KeyCode::Left => settings.adjust(item, false),
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
+                        screen = AppScreen::Menu
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        // Leaderboard input handling
+        if screen == AppScreen::Leaderboard {
+            if let Some(Event::Key(KeyEvent {
+                code: KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q'),
+                ..
+            })) = poll_event(Duration::from_millis(200))?
+            {
+                screen = AppScreen::Menu;
+            }
+            continue;
+        }
+
         // Main game loop
         if let Some(game) = game_opt.as_mut() {
-            let tick_dur = game.tick_duration();
             let mut last_tick = Instant::now();
+            // Only tracked when the debug overlay is on
+            let mut frame_stats = args.debug.then(FrameStats::default);
+            // Quitting mid-game asks first, holding the game meanwhile
+            let mut confirm_quit = false;
+            // Last turn key seen, so auto-repeat from a held key is ignored
+            let mut last_move: Option<(usize, DirectionEnum)> = None;
+            let mut text_log = args.text_mode.then(TextLog::default);
+            // Set by steps and input; the screen is only redrawn when dirty
+            // or after IDLE_REDRAW
+            let mut dirty = true;
+            let mut last_draw = Instant::now();
+            // Phase of a pulsing apple in the last frame drawn
+            let mut drawn_pulse = apple_dimmed(game);
 
             loop {
-                terminal.draw(|f| {
-                    draw_game(f, game, f.size());
-                })?;
+                // Re-read every frame so speed follows the level as it rises
+                let tick_dur = game.tick_duration();
+                if apple_dimmed(game) != drawn_pulse {
+                    dirty = true;
+                }
+                if dirty || last_draw.elapsed() >= IDLE_REDRAW {
+                    terminal.draw(|f| {
+                        if let Some(log) = &text_log {
+                            draw_text_log(f, f.size(), log);
+                        } else {
+                            draw_game(
+                                f,
+                                game,
+                                game.theme(),
+                                &Glyphs::new(game.theme(), settings.ascii),
+                                keys,
+                                f.size(),
+                            );
+                        }
+                        if let Some(stats) = &frame_stats {
+                            draw_debug(
+                                f,
+                                f.size(),
+                                stats,
+                                tick_dur,
+                                game.base_tick_ms,
+                                game.snakes[0].body.len(),
+                            );
+                        }
+                        if confirm_quit {
+                            draw_banner(
+                                f,
+                                f.size(),
+                                "Quit? (y/n)",
+                                Style::default()
+                                    .fg(game.theme().alert.tui())
+                                    .add_modifier(Modifier::BOLD),
+                            );
+                        }
+                    })?;
+                    if let Some(stats) = frame_stats.as_mut() {
+                        stats.record();
+                    }
+                    dirty = false;
+                    last_draw = Instant::now();
+                    drawn_pulse = apple_dimmed(game);
+                    if args.title {
+                        update_title(&mut shown_title, game)?;
+                    }
+                }
 
-                let timeout = Duration::from_millis(16);
-                if event::poll(timeout)? {
-                    match event::read()? {
-                        // Quit game
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('q'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Char('Q'),
-                            ..
-                        }) => return Ok(()),
-                        // Restart game instantly
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('r'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Char('R'),
+                // Sleep in the input poll until the next update is due, so an
+                // idle or slow game doesn't spin
+                let snake = &game.snakes[0];
+                let next_update = if game.paused || confirm_quit {
+                    IDLE_REDRAW
+                } else if game.countdown.is_some() {
+                    COUNTDOWN_STEP
+                } else if settings.quick_turns
+                    && !game.is_versus()
+                    && snake.dir_queue.front().is_some_and(|&d| d != snake.dir)
+                {
+                    tick_dur / 2
+                } else {
+                    tick_dur
+                };
+                let timeout = next_update
+                    .saturating_sub(last_tick.elapsed())
+                    .min(IDLE_REDRAW.saturating_sub(last_draw.elapsed()))
+                    .min(until_pulse(game));
+                let arrow_player = if game.is_versus() { 1 } else { 0 };
+                if let Some(event) = poll_event(timeout)? {
+                    dirty = true;
+                    match event {
+                        // Only y confirms; any other key picks up where we left off
+                        Event::Key(KeyEvent { code, .. }) if confirm_quit => {
+                            if let KeyCode::Char('y') | KeyCode::Char('Y') = code {
+                                return Ok(Some(game.seed));
+                            }
+                            confirm_quit = false;
+                        }
+                        // Any key closes the help overlay and resumes play
+                        Event::Key(_) if game.show_help => {
+                            game.show_help = false;
+                            game.paused = false;
+                        }
+                        Event::Key(KeyEvent { code, .. }) => {
+                            let action = keys.action(code);
+                            // Unbound arrows steer player two in versus mode
+                            let turn = match action {
+                                Some(Action::Move(d)) => Some((0, d)),
+                                None => arrow_direction(code).map(|d| (arrow_player, d)),
+                                _ => None,
+                            };
+                            let repeated = turn.is_some() && turn == last_move;
+                            last_move = turn;
+                            match action {
+                                Some(Action::Quit) => confirm_quit = true,
+                                // Restart game instantly; the abandoned run is
+                                // neither recorded nor scored
+                                Some(Action::Restart | Action::Menu) if game.hardcore => {}
+                                Some(Action::Restart) => {
+                                    let size = terminal.get_frame().size();
+                                    *game = match campaign_index {
+                                        Some(index) => {
+                                            level_game(index, campaign_length, &settings)
+                                        }
+                                        None => new_game(size, &settings),
+                                    };
+                                    continue 'app;
+                                }
+                                Some(Action::Menu) => {
+                                    game_opt = None;
+                                    campaign_index = None;
+                                    screen = AppScreen::Menu;
+                                    continue 'app;
+                                }
+                                Some(Action::Pause) => game.paused = !game.paused,
+                                // --debug only: step back one tick while paused
+                                Some(Action::Undo) => {
+                                    if game.paused {
+                                        game.undo();
+                                    }
+                                }
+                                Some(Action::Autopilot) => game.autopilot = !game.autopilot,
+                                Some(Action::Boost) => {
+                                    game.boost_until = Some(Instant::now() + BOOST_DURATION)
+                                }
+                                Some(Action::Practice) => game.toggle_invincible(),
+                                // The new theme sticks for later games and
+                                // sessions
+                                Some(Action::Theme) => {
+                                    game.cycle_theme();
+                                    settings.theme = game.theme;
+                                    settings.random_theme = false;
+                                    if let Some(path) = settings_path() {
+                                        let _ = settings.save(&path);
+                                    }
+                                }
+                                Some(Action::Help) => {
+                                    game.show_help = true;
+                                    game.paused = true;
+                                }
+                                Some(Action::Faster) => game.adjust_base_tick(true),
+                                Some(Action::Slower) => game.adjust_base_tick(false),
+                                // Relative controls take every press, since a
+                                // quick double tap is how they U-turn
+                                Some(Action::Move(_)) | None if settings.relative_turns => {
+                                    match turn {
+                                        Some((player, DirectionEnum::Left)) => {
+                                            game.turn_left(player)
+                                        }
+                                        Some((player, DirectionEnum::Right)) => {
+                                            game.turn_right(player)
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                                Some(Action::Move(_)) | None => {
+                                    if let Some((player, d)) = turn
+                                        && !repeated
+                                    {
+                                        game.set_direction(player, d);
+                                    }
+                                }
+                            }
+                        }
+                        // Losing focus pauses; resuming waits for the player
+                        Event::FocusLost if args.pause_on_blur => game.paused = true,
+                        // Clicking a board cell steers player one toward it
+                        Event::Mouse(MouseEvent {
+                            kind: MouseEventKind::Down(_),
+                            column,
+                            row,
                             ..
                         }) => {
-                            let size = terminal.get_frame().size();
-                            *game = Game::new(size);
-                            break;
+                            let board = board_cells(
+                                game,
+                                board_rect(terminal.get_frame().size(), game.settings.spacing),
+                            );
+                            let view = viewport(game, board);
+                            if column >= board.x && row >= board.y {
+                                let (col, row) =
+                                    ((column - board.x) / game.cell_width(), row - board.y);
+                                if col < view.width && row < view.height {
+                                    game.steer_toward(Point {
+                                        x: view.x + col,
+                                        y: view.y + row,
+                                    });
+                                }
+                            }
+                        }
+                        // Keep the board in step with the terminal size, except
+                        // for sizes fixed by flags or a campaign map
+                        Event::Resize(w, h)
+                            if campaign_index.is_none() && custom_level.is_none() =>
+                        {
+                            let (width, height) =
+                                Game::sized_board(args.width, args.height, w, h, &settings);
+                            game.resize(width, height);
                         }
-                        // Movement keys
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('w'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Up, ..
-                        }) => game.set_direction(DirectionEnum::Up),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('s'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Down,
-                            ..
-                        }) => game.set_direction(DirectionEnum::Down),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('a'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Left,
-                            ..
-                        }) => game.set_direction(DirectionEnum::Left),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('d'),
-                            ..
-                        })
-                        | Event::Key(KeyEvent {
-                            code: KeyCode::Right,
-                            ..
-                        }) => game.set_direction(DirectionEnum::Right),
                         _ => {}
                     }
                 }
 
-                // Update game state every tick
-                if last_tick.elapsed() >= tick_dur {
+                // Quick turns: a turn waiting past the middle of a tick moves
+                // at once, and the time saved is added to the next tick so the
+                // snake never averages faster than the base rate
+                let snake = &game.snakes[0];
+                if settings.quick_turns
+                    && !game.is_versus()
+                    && !game.paused
+                    && !confirm_quit
+                    && game.countdown.is_none()
+                    && snake.dir_queue.front().is_some_and(|&d| d != snake.dir)
+                    && last_tick.elapsed() >= tick_dur / 2
+                {
+                    let early_by = tick_dur.saturating_sub(last_tick.elapsed());
                     game.step();
+                    last_tick = Instant::now() + early_by;
+                    dirty = true;
+                }
+
+                // Count down before the first move, then update every tick
+                if game.paused || confirm_quit {
+                    // Hold the time-attack clock while paused
+                    game.start += last_tick.elapsed();
                     last_tick = Instant::now();
+                } else if game.countdown.is_some() {
+                    if last_tick.elapsed() >= COUNTDOWN_STEP {
+                        game.tick_countdown();
+                        last_tick = Instant::now();
+                        dirty = true;
+                    }
+                } else if last_tick.elapsed() >= tick_dur {
+                    // Fixed timestep: the next tick is due one tick after this
+                    // one was, unless the loop fell a whole tick behind
+                    game.step();
+                    last_tick += tick_dur;
+                    if last_tick.elapsed() >= tick_dur {
+                        last_tick = Instant::now();
+                    }
+                    dirty = true;
+                }
+                game.check_time();
+                handle_events(game, sound.as_ref());
+                if let Some(log) = text_log.as_mut() {
+                    log.update(game);
                 }
 
-                // Exit inner loop on Game Over
-                if game.game_over {
+                // Exit inner loop on Game Over or a cleared level
+                if game.game_over || game.cleared {
                     break;
                 }
             }
 
+            // Crashed snakes are eaten away before the game-over screen;
+            // any key skips ahead
+            let dead_length = game
+                .snakes
+                .iter()
+                .filter(|s| !s.alive)
+                .map(|s| s.body.len())
+                .max();
+            if let Some(length) = dead_length
+                && game.game_over
+                && settings.death_animation
+                && text_log.is_none()
+            {
+                let start = Instant::now();
+                while start.elapsed() < DEATH_ANIMATION {
+                    game.vanished = (length as f64 * start.elapsed().as_secs_f64()
+                        / DEATH_ANIMATION.as_secs_f64())
+                        as usize;
+                    terminal.draw(|f| {
+                        let theme = game.theme();
+                        draw_game(
+                            f,
+                            game,
+                            theme,
+                            &Glyphs::new(theme, settings.ascii),
+                            keys,
+                            f.size(),
+                        );
+                    })?;
+                    if let Some(Event::Key(_)) = poll_event(Duration::from_millis(16))? {
+                        break;
+                    }
+                }
+                game.vanished = length;
+            }
+
+            // A cleared campaign level leads to the next map, or to the
+            // victory screen after the last one
+            if game.cleared
+                && let Some(index) = campaign_index
+            {
+                let next = index + 1;
+                let text = match levels.get(next) {
+                    Some(level) => format!("LEVEL CLEARED - Enter for {}", level.name),
+                    None => "VICTORY! Campaign complete - Enter for the menu".to_string(),
+                };
+                loop {
+                    terminal.draw(|f| {
+                        let theme = game.theme();
+                        draw_game(
+                            f,
+                            game,
+                            theme,
+                            &Glyphs::new(theme, settings.ascii),
+                            keys,
+                            f.size(),
+                        );
+                        draw_banner(
+                            f,
+                            board_rect(f.size(), game.settings.spacing),
+                            &text,
+                            Style::default()
+                                .fg(theme.title.tui())
+                                .add_modifier(Modifier::BOLD),
+                        );
+                    })?;
+                    if let Some(Event::Key(KeyEvent { code, .. })) =
+                        poll_event(Duration::from_millis(200))?
+                    {
+                        if code == KeyCode::Enter {
+                            break;
+                        }
+                        if keys.action(code) == Some(Action::Quit) {
+                            return Ok(Some(game.seed));
+                        }
+                    }
+                }
+                campaign_length = game.snakes[0].body.len() as u16;
+                if next < levels.len() {
+                    *game = level_game(next, campaign_length, &settings);
+                    campaign_index = Some(next);
+                } else {
+                    campaign_index = None;
+                    screen = AppScreen::Menu;
+                }
+                continue;
+            }
+
+            // Record the finished run; failing to save must not end the session.
+            // Practice toggles, campaign maps and --level boards aren't
+            // recorded, so those runs can't be replayed
+            if !game.practice
+                && campaign_index.is_none()
+                && custom_level.is_none()
+                && let Some(path) = last_replay_path()
+            {
+                let _ = game.replay().save(&path);
+            }
+            // A time-attack run beating the best one for its clock becomes
+            // the next ghost
+            if game.time_up
+                && !game.practice
+                && custom_level.is_none()
+                && !game.is_versus()
+                && let Some(path) = game.time_limit.and_then(best_run_path)
+                && Replay::load(&path).map_or(true, |best| game.score > best.score)
+            {
+                let _ = game.replay().save(&path);
+            }
+            if game.game_over
+                && let Some(path) = &args.dump
+            {
+                let _ = fs::write(path, game.to_json() + "\n");
+            }
+
+            // Solo scores good enough for the leaderboard ask for initials;
+            // practice, campaign, --level and daily runs never rank. Only
+            // points earned in this game count, not any carried-over bonus
+            let mut board = leaderboard_path()
+                .map(|path| Leaderboard::load(&path))
+                .unwrap_or_default();
+            let solo_over = game.game_over && !game.is_versus();
+            let ranked = solo_over
+                && !game.practice
+                && campaign_index.is_none()
+                && custom_level.is_none()
+                && game.daily.is_none();
+            let mut initials = (ranked && board.qualifies(game.earned())).then(String::new);
+            let new_record = ranked
+                && game.earned() > 0
+                && board
+                    .entries
+                    .first()
+                    .is_none_or(|(_, best)| game.earned() > *best);
+            let play_time = game.start.elapsed();
+            // The all-time best only changes when it's strictly beaten
+            if ranked && game.earned() > game.high_score {
+                let _ = save_high_score(game.earned());
+            }
+
+            // Daily challenges keep their own best, one per day
+            let mut new_daily_best = false;
+            if solo_over
+                && !game.practice
+                && game.score > 0
+                && let Some(date) = &game.daily
+                && let Some(path) = daily_path()
+            {
+                let mut best = DailyBests::load(&path);
+                new_daily_best = best.record(date, game.score);
+                if new_daily_best {
+                    let _ = best.save(&path);
+                }
+            }
+
+            // A finished speedrun keeps its splits if they beat the best
+            let mut new_best_splits = false;
+            if game.cleared
+                && campaign_index.is_none()
+                && !game.practice
+                && let Some(goal) = game.speedrun_goal
+                && let Some(path) = splits_path()
+            {
+                let mut best = BestSplits::load(&path);
+                new_best_splits = best.record(goal, &game.splits);
+                if new_best_splits {
+                    let _ = best.save(&path);
+                }
+            }
+
             // Game over loop: wait for R or Q
             loop {
-                terminal.draw(|f| draw_game(f, game, f.size()))?;
-                if event::poll(Duration::from_millis(200))? {
-                    if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+                terminal.draw(|f| {
+                    let theme = game.theme();
+                    if let Some(log) = &text_log {
+                        draw_text_log(f, f.size(), log);
+                    } else {
+                        draw_game(
+                            f,
+                            game,
+                            theme,
+                            &Glyphs::new(theme, settings.ascii),
+                            keys,
+                            f.size(),
+                        );
+                    }
+                    if solo_over {
+                        draw_stats(
+                            f,
+                            f.size(),
+                            game,
+                            play_time,
+                            new_record || new_daily_best,
+                            initials.as_deref(),
+                            keys,
+                        );
+                    } else if game.cleared && campaign_index.is_none() {
+                        draw_splits(f, f.size(), game, new_best_splits, theme, keys);
+                    }
+                })?;
+                if let Some(Event::Key(KeyEvent { code, .. })) =
+                    poll_event(Duration::from_millis(200))?
+                {
+                    // Typing initials takes every key until they're saved
+                    if let Some(name) = initials.as_mut() {
+                        let mut finished = code == KeyCode::Esc;
                         match code {
-                            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(()),
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                let size = terminal.get_frame().size();
-                                *game = Game::new(size);
-                                break;
+                            KeyCode::Char(c)
+                                if c.is_ascii_alphanumeric() && name.len() < INITIALS_LEN =>
+                            {
+                                name.push(c.to_ascii_uppercase())
+                            }
+                            KeyCode::Backspace => {
+                                name.pop();
+                            }
+                            KeyCode::Enter if name.len() == INITIALS_LEN => {
+                                board.insert(name.clone(), game.earned());
+                                if let Some(path) = leaderboard_path() {
+                                    let _ = board.save(&path);
+                                }
+                                finished = true;
                             }
                             _ => {}
                         }
+                        if finished {
+                            initials = None;
+                        }
+                        continue;
+                    }
+                    match keys.action(code) {
+                        Some(Action::Quit) => return Ok(Some(game.seed)),
+                        // Hardcore runs end here; relaunching is the only way
+                        // to play again
+                        Some(Action::Restart | Action::Menu) if game.hardcore => {}
+                        Some(Action::Restart) => {
+                            let size = terminal.get_frame().size();
+                            let bonus = game.carry_bonus();
+                            *game = match campaign_index {
+                                Some(index) => level_game(index, campaign_length, &settings),
+                                None => new_game(size, &settings),
+                            };
+                            game.start_with_bonus(bonus);
+                            break;
+                        }
+                        Some(Action::Menu) => {
+                            game_opt = None;
+                            campaign_index = None;
+                            screen = AppScreen::Menu;
+                            break;
+                        }
+                        _ => {}
                     }
                 }
             }
         }
     }
 }
+
+/// Plays back a recorded run without reading live input (Q exits early)
+fn run_replay<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    replay: &Replay,
+) -> io::Result<()> {
+    let mut game = Game::for_replay(replay);
+    let theme = game.theme();
+    let glyphs = Glyphs::new(theme, replay.settings.ascii);
+    let keys = KeyMap::default();
+    let mut last_tick = Instant::now();
+
+    loop {
+        terminal.draw(|f| draw_game(f, &game, theme, &glyphs, &keys, f.size()))?;
+
+        if let Some(Event::Key(KeyEvent {
+            code: KeyCode::Char('q') | KeyCode::Char('Q'),
+            ..
+        })) = poll_event(Duration::from_millis(16))?
+        {
+            return Ok(());
+        }
+
+        // Feed recorded resizes and turns in at the tick they happened
+        if !game.game_over && last_tick.elapsed() >= game.tick_duration() {
+            let tick = game.ticks;
+            for &(_, w, h) in replay.resizes.iter().filter(|r| r.0 == tick) {
+                game.resize(w, h);
+            }
+            for &(_, player, d) in replay.turns.iter().filter(|t| t.0 == tick) {
+                if let Some(snake) = game.snakes.get_mut(player) {
+                    snake.dir_queue.push_back(d);
+                }
+            }
+            game.step();
+            handle_events(&mut game, None);
+            last_tick = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn key_map_parses_rebinds_and_rejects_conflicts() {
+        let text = "# vim keys\nup = \"k\"\nquit = [\"x\", \"Esc\"]\npause = \"nope\"\n";
+        let (keys, warnings) = KeyMap::parse(text).unwrap();
+        assert_eq!(
+            keys.action(KeyCode::Char('k')),
+            Some(Action::Move(DirectionEnum::Up))
+        );
+        assert_eq!(keys.action(KeyCode::Char('w')), None);
+        assert_eq!(keys.action(KeyCode::Esc), Some(Action::Quit));
+        assert_eq!(keys.action(KeyCode::Char('q')), None);
+        // The invalid pause binding keeps its default
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(keys.action(KeyCode::Char(' ')), Some(Action::Pause));
+
+        // A rebound key leaves its default action; only two rebinds clash
+        let (keys, _) = KeyMap::parse("menu = \"h\"\npause = \"q\"").unwrap();
+        assert_eq!(keys.action(KeyCode::Char('h')), Some(Action::Menu));
+        assert_eq!(
+            keys.action(KeyCode::Char('H')),
+            Some(Action::Move(DirectionEnum::Left))
+        );
+        assert_eq!(keys.action(KeyCode::Char('q')), Some(Action::Pause));
+        assert_eq!(keys.action(KeyCode::Char('Q')), Some(Action::Quit));
+        assert!(KeyMap::parse("pause = \"x\"\nquit = \"x\"").is_err());
+    }
+
+    #[test]
+    fn frame_recorder_writes_only_changed_frames() {
+        let backend = FrameRecorder::new(TestBackend::new(4, 1), Some(Vec::new()));
+        let mut terminal = Terminal::new(backend).unwrap();
+        let draw = |terminal: &mut Terminal<_>, text: &'static str| {
+            terminal
+                .draw(|f| {
+                    let style = Style::default().fg(Color::Red);
+                    f.render_widget(Paragraph::new(Span::styled(text, style)), f.size())
+                })
+                .unwrap();
+        };
+        draw(&mut terminal, "ab");
+        draw(&mut terminal, "ab");
+        draw(&mut terminal, "ac");
+        let text = String::from_utf8(terminal.backend().out.clone().unwrap()).unwrap();
+        let frames: Vec<_> = text.split("\x1b[H\x1b[2J").skip(1).collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], "\x1b[0;31;49mab\x1b[0;39;49m  \x1b[0m\r\n");
+        assert!(frames[1].contains("ac"));
+    }
+
+    #[test]
+    fn whole_board_is_shown_for_every_spacing() {
+        let area = Rect::new(0, 0, 80, 24);
+        for spacing in Spacing::ALL {
+            for (ruler, square_cells) in [(false, false), (true, false), (true, true)] {
+                let settings = Settings {
+                    spacing,
+                    ruler,
+                    square_cells,
+                    ..Settings::default()
+                };
+                let game = Game::new(area.width, area.height, &settings);
+                let view = viewport(&game, board_cells(&game, board_rect(area, spacing)));
+                assert_eq!((view.width, view.height), (game.width, game.height));
+            }
+        }
+    }
+
+    #[test]
+    fn line_bodies_follow_their_neighbors_across_wrapped_edges() {
+        let settings = Settings {
+            wrap: WrapMode::Both,
+            ..Settings::default()
+        };
+        let game = Game::from_board(20, 10, &settings, 1);
+        let corner = Point { x: 0, y: 0 };
+        let links: Vec<DirectionEnum> = [Point { x: 19, y: 0 }, Point { x: 0, y: 1 }]
+            .into_iter()
+            .filter_map(|q| game.link(corner, q))
+            .collect();
+        assert_eq!(links, vec![DirectionEnum::Left, DirectionEnum::Down]);
+        assert_eq!(line_glyph(&links, false), Some("┐"));
+        assert_eq!(line_glyph(&links[..1], true), Some("-"));
+        assert_eq!(game.link(corner, Point { x: 5, y: 5 }), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Headless games driven through the library's public API
+
+use snake_game::{DirectionEnum, Game, Point, Settings};
+
+/// A game that moves from the first tick, with the apple out of the way
+fn game() -> Game {
+    let mut game = Game::from_board(20, 10, &Settings::default(), 1);
+    game.countdown = None;
+    game.apple = Point { x: 0, y: 0 };
+    game
+}
+
+#[test]
+fn snake_moves_and_turns_one_cell_per_step() {
+    let mut game = game();
+    let start = game.snakes[0].head();
+    let length = game.snakes[0].body.len();
+
+    game.step();
+    assert!(
+        game.snakes[0].head()
+            == Point {
+                x: start.x + 1,
+                y: start.y
+            }
+    );
+
+    game.set_direction(0, DirectionEnum::Down);
+    game.step();
+    game.step();
+    assert!(
+        game.snakes[0].head()
+            == Point {
+                x: start.x + 1,
+                y: start.y + 2
+            }
+    );
+    assert_eq!(game.snakes[0].body.len(), length);
+    assert!(!game.game_over);
+}
+
+#[test]
+fn eating_an_apple_scores_grows_and_places_another() {
+    let mut game = game();
+    let head = game.snakes[0].head();
+    let length = game.snakes[0].body.len();
+    let apple = Point {
+        x: head.x + 1,
+        y: head.y,
+    };
+    game.apple = apple;
+
+    game.step();
+    assert_eq!(game.score, 1);
+    assert!(game.apple != apple);
+    assert!(!game.occupied.contains(&game.apple));
+    // The tail stays put on the step that eats the apple
+    assert_eq!(game.snakes[0].body.len(), length + 1);
+    game.step();
+    assert_eq!(game.snakes[0].body.len(), length + 1);
+}
+
+#[test]
+fn running_into_the_wall_ends_the_game() {
+    let mut game = game();
+    let head = game.snakes[0].head();
+    for _ in head.x..20 {
+        game.step();
+    }
+    assert!(game.game_over);
+}